@@ -0,0 +1,564 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use armory_staking::account::{GlobalPool, Leaderboard, RentalListing, StakeRecord, UserPool};
+use armory_staking::constant::BOX_COLLECTION_ADDRESS;
+use armory_staking_client::{instruction, pda, reward, state};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    account::from_account,
+    clock::Clock,
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    sysvar,
+    transaction::Transaction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::rpc::Rpc;
+use crate::CliResult;
+
+fn send(rpc: &dyn Rpc, payer: &Keypair, instructions: &[Instruction]) -> CliResult {
+    send_signed(rpc, payer, &[], instructions)
+}
+
+fn send_signed(
+    rpc: &dyn Rpc,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    instructions: &[Instruction],
+) -> CliResult {
+    let blockhash = rpc.latest_blockhash()?;
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    let signature = rpc.send_and_confirm(&transaction)?;
+    println!("txHash = {}", signature);
+    Ok(())
+}
+
+fn create_token_account(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    create_associated_token_account_idempotent(payer, owner, mint, &spl_token::ID)
+}
+
+fn now(rpc: &dyn Rpc) -> CliResult<i64> {
+    let clock = rpc.account(&sysvar::clock::ID)?.ok_or("no clock")?;
+    let clock: Clock = from_account(&clock).ok_or("bad clock")?;
+    Ok(clock.unix_timestamp)
+}
+
+fn global_pool(rpc: &dyn Rpc) -> CliResult<GlobalPool> {
+    let data = rpc.account_data(&pda::global_authority().0)?;
+    Ok(state::decode_global_pool(&data).map_err(|err| err.to_string())?)
+}
+
+fn user_pool(rpc: &dyn Rpc, owner: &Pubkey) -> CliResult<Option<UserPool>> {
+    let account = rpc.account(&pda::user_pool(owner, 0))?;
+    match account {
+        Some(account) => Ok(Some(
+            state::decode_user_pool(&account.data).map_err(|err| err.to_string())?,
+        )),
+        None => Ok(None),
+    }
+}
+
+fn leaderboard(rpc: &dyn Rpc) -> CliResult<Option<Leaderboard>> {
+    let account = rpc.account(&pda::leaderboard(0).0)?;
+    match account {
+        Some(account) => Ok(Some(
+            state::decode_leaderboard(&account.data).map_err(|err| err.to_string())?,
+        )),
+        None => Ok(None),
+    }
+}
+
+fn init_user_instructions(rpc: &dyn Rpc, owner: &Pubkey) -> CliResult<Vec<Instruction>> {
+    let lamports = rpc.minimum_balance_for_rent_exemption(state::USER_POOL_SPACE)?;
+    Ok(vec![
+        instruction::create_user_pool(owner, 0, lamports),
+        instruction::init_user_pool(owner),
+    ])
+}
+
+fn parse_node(node: &str) -> CliResult<[u8; 32]> {
+    let node = node.trim_start_matches("0x");
+    if node.len() != 64 {
+        return Err(format!("proof node {} is not 32 bytes of hex", node).into());
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&node[2 * i..2 * i + 2], 16)?;
+    }
+    Ok(bytes)
+}
+
+pub fn init_global(rpc: &dyn Rpc, payer: &Keypair) -> CliResult {
+    send(rpc, payer, &[instruction::initialize(&payer.pubkey())])
+}
+
+pub fn migrate_global(rpc: &dyn Rpc, payer: &Keypair) -> CliResult {
+    send(
+        rpc,
+        payer,
+        &[instruction::migrate_global_pool(&payer.pubkey())],
+    )
+}
+
+pub fn init_user(rpc: &dyn Rpc, payer: &Keypair) -> CliResult {
+    let instructions = init_user_instructions(rpc, &payer.pubkey())?;
+    send(rpc, payer, &instructions)
+}
+
+pub fn stake(
+    rpc: &dyn Rpc,
+    payer: &Keypair,
+    bear_mint: &Pubkey,
+    bear_box: Option<(Pubkey, u64)>,
+    rarity_rate: u64,
+    rarity_proof: &[String],
+) -> CliResult {
+    let owner = payer.pubkey();
+    let global_authority = pda::global_authority().0;
+    let rarity_proof = rarity_proof
+        .iter()
+        .map(|node| parse_node(node))
+        .collect::<CliResult<Vec<_>>>()?;
+
+    let mut instructions = vec![];
+    if user_pool(rpc, &owner)?.is_none() {
+        instructions.extend(init_user_instructions(rpc, &owner)?);
+    }
+    instructions.push(create_token_account(&owner, &global_authority, bear_mint));
+    if let Some((box_mint, _)) = bear_box {
+        instructions.push(create_token_account(&owner, &global_authority, &box_mint));
+    }
+    instructions.push(create_token_account(&owner, &owner, &pda::medal_mint()));
+    instructions.push(instruction::stake_nft(
+        &owner,
+        bear_mint,
+        bear_box,
+        rarity_rate,
+        rarity_proof,
+    ));
+    send(rpc, payer, &instructions)
+}
+
+pub fn unstake(rpc: &dyn Rpc, payer: &Keypair, bear_mint: &Pubkey, claim: bool) -> CliResult {
+    let owner = payer.pubkey();
+    let user_pool = user_pool(rpc, &owner)?.ok_or("user pool is not initialized")?;
+    let index = user_pool
+        .find_nft(bear_mint)
+        .ok_or("bear is not staked in this user pool")?;
+    let staked = user_pool.staked_nfts[index];
+    let bear_box = (staked.box_id != 0).then_some((staked.box_mint, staked.box_id));
+
+    let destination = user_pool.reward_destination();
+    let mut instructions = vec![];
+    if claim {
+        instructions.push(create_token_account(
+            &owner,
+            &destination,
+            &pda::medal_mint(),
+        ));
+    }
+    instructions.push(instruction::unstake_nft(
+        &owner,
+        bear_mint,
+        bear_box,
+        claim.then_some(&destination),
+    ));
+    send(rpc, payer, &instructions)
+}
+
+pub fn mint_receipt(rpc: &dyn Rpc, payer: &Keypair, bear_mint: &Pubkey) -> CliResult {
+    let owner = payer.pubkey();
+    let user_pool = user_pool(rpc, &owner)?.ok_or("user pool is not initialized")?;
+    user_pool
+        .find_nft(bear_mint)
+        .ok_or("bear is not staked in this user pool")?;
+
+    let receipt_mint = Keypair::new();
+    println!("receipt mint = {}", receipt_mint.pubkey());
+    send_signed(
+        rpc,
+        payer,
+        &[&receipt_mint],
+        &[instruction::mint_receipt(
+            &owner,
+            bear_mint,
+            &receipt_mint.pubkey(),
+        )],
+    )
+}
+
+pub fn unstake_receipt(rpc: &dyn Rpc, payer: &Keypair, receipt_mint: &Pubkey) -> CliResult {
+    let holder = payer.pubkey();
+    let data = rpc.account_data(&pda::receipt(receipt_mint).0)?;
+    let receipt = state::decode_stake_receipt(&data).map_err(|err| err.to_string())?;
+
+    let mut instructions = vec![create_token_account(&holder, &holder, &receipt.bear_mint)];
+    if receipt.box_id != 0 {
+        instructions.push(create_token_account(&holder, &holder, &receipt.box_mint));
+    }
+    instructions.push(create_token_account(&holder, &holder, &pda::medal_mint()));
+    instructions.push(instruction::unstake_receipt(
+        &holder,
+        receipt_mint,
+        &receipt,
+    ));
+    send(rpc, payer, &instructions)
+}
+
+pub fn claim(
+    rpc: &dyn Rpc,
+    payer: &Keypair,
+    owner: Option<Pubkey>,
+    allow_partial: bool,
+    instant: bool,
+    reward_token_slots: &[u8],
+) -> CliResult {
+    let claimer = payer.pubkey();
+    let owner = owner.unwrap_or(claimer);
+    let global = global_pool(rpc)?;
+    let user_pool = user_pool(rpc, &owner)?.ok_or("user pool is not initialized")?;
+    let destination = user_pool.reward_destination();
+
+    let mut instructions = vec![create_token_account(
+        &claimer,
+        &destination,
+        &pda::medal_mint(),
+    )];
+    if global.vesting_duration > 0 && !instant {
+        let schedule = rpc.account(&pda::vesting(&owner).0)?;
+        if schedule.is_none() {
+            if claimer != owner {
+                return Err(
+                    "the owner has no vesting schedule yet, claim once as the owner".into(),
+                );
+            }
+            instructions.push(instruction::init_vesting(&owner));
+        }
+    }
+    let mut reward_tokens = vec![];
+    for slot in reward_token_slots {
+        let config = global
+            .reward_tokens
+            .get(*slot as usize)
+            .filter(|config| config.mint != Pubkey::default())
+            .ok_or_else(|| format!("no reward token in slot {}", slot))?;
+        instructions.push(create_token_account(&claimer, &destination, &config.mint));
+        reward_tokens.push((*slot, config.mint));
+    }
+    instructions.push(instruction::claim_reward(
+        &claimer,
+        &owner,
+        &destination,
+        allow_partial,
+        instant,
+        &reward_tokens,
+    ));
+    send(rpc, payer, &instructions)
+}
+
+pub fn fund_vault(rpc: &dyn Rpc, payer: &Keypair, amount: u64) -> CliResult {
+    let funder = payer.pubkey();
+    let medal_mint = pda::medal_mint();
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &pda::token_account(&funder, &medal_mint),
+        &pda::reward_vault(),
+        &funder,
+        &[],
+        amount,
+    )?;
+    send(
+        rpc,
+        payer,
+        &[
+            create_token_account(&funder, &pda::global_authority().0, &medal_mint),
+            transfer,
+        ],
+    )
+}
+
+pub fn show_global(rpc: &dyn Rpc) -> CliResult {
+    let global = global_pool(rpc)?;
+    println!("GlobalAuthority: {}", pda::global_authority().0);
+    println!("RewardVault: {}", pda::reward_vault());
+    println!("SolVault: {}", pda::sol_vault().0);
+    println!("super_admin: {}", global.super_admin);
+    println!("total_staked_count: {}", global.total_staked_count);
+    println!("emission: {:?}", global.emission);
+    println!("vesting_duration: {}", global.vesting_duration);
+    println!("instant_haircut_bps: {}", global.instant_haircut_bps);
+    println!(
+        "fees: stake {} unstake {} claim {}",
+        global.stake_fee, global.unstake_fee, global.claim_fee
+    );
+    for (slot, config) in global.reward_tokens.iter().enumerate() {
+        if config.mint != Pubkey::default() {
+            println!(
+                "reward_token[{}]: {} rate_per_medal {} since {}",
+                slot, config.mint, config.rate_per_medal, config.start_time
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn show_user(rpc: &dyn Rpc, owner: &Pubkey) -> CliResult {
+    let user_pool = user_pool(rpc, owner)?.ok_or("user pool is not initialized")?;
+    let now = now(rpc)?;
+    println!("UserPool: {}", pda::user_pool(owner, 0));
+    println!("owner: {}", user_pool.owner);
+    println!("version: {}", user_pool.version);
+    println!("last_claimed_time: {}", user_pool.last_claimed_time);
+    println!("pending_reward: {}", user_pool.pending_reward);
+    println!("mission_completed: {}", user_pool.mission_completed);
+    println!("claim_delegate: {}", user_pool.claim_delegate);
+    println!("reward_destination: {}", user_pool.reward_destination());
+    println!("points: {}", user_pool.points);
+    println!("set_bonus_bps: {}", user_pool.set_bonus_bps);
+    println!("staked_count: {}", user_pool.staked_count);
+    for staked in user_pool.staked_nfts[..user_pool.staked_count as usize].iter() {
+        println!(
+            "  bear {} #{} box {} #{} staked_time {} reward_rate {} streak +{}bps",
+            staked.bear_mint,
+            staked.bear_id,
+            staked.box_mint,
+            staked.box_id,
+            staked.staked_time,
+            staked.reward_rate,
+            staked.streak(now).bonus_bps
+        );
+    }
+    Ok(())
+}
+
+pub fn pending(rpc: &dyn Rpc, owner: &Pubkey) -> CliResult {
+    let global = global_pool(rpc)?;
+    let user_pool = user_pool(rpc, owner)?.ok_or("user pool is not initialized")?;
+    let now = now(rpc)?;
+
+    let pending = reward::pending_reward(&user_pool, &global, now).map_err(|e| e.to_string())?;
+    let claimable =
+        reward::claimable_reward(&user_pool, &global, now).map_err(|e| e.to_string())?;
+    let daily = reward::daily_reward(&user_pool).map_err(|e| e.to_string())?;
+    let points = reward::pending_points(&user_pool, now).map_err(|e| e.to_string())?;
+    println!("pending: {}", pending);
+    println!("claimable now: {}", claimable);
+    println!("daily rate: {}", daily);
+    println!("points: {}", points);
+    Ok(())
+}
+
+pub fn show_leaderboard(rpc: &dyn Rpc) -> CliResult {
+    let leaderboard = leaderboard(rpc)?.ok_or("leaderboard is not created")?;
+    println!("Leaderboard: {}", pda::leaderboard(0).0);
+    for (rank, entry) in leaderboard.entries.iter().enumerate() {
+        if entry.owner != Pubkey::default() {
+            println!("  {:>2}. {} {}", rank + 1, entry.owner, entry.points);
+        }
+    }
+    Ok(())
+}
+
+fn rental_listing(rpc: &dyn Rpc, bear_mint: &Pubkey, owner: &Pubkey) -> CliResult<RentalListing> {
+    let data = rpc.account_data(&pda::rental_listing(bear_mint, owner).0)?;
+    Ok(state::decode_rental_listing(&data).map_err(|err| err.to_string())?)
+}
+
+/// Burn a box to raise a bear one tier, the box is assumed to sit in the verified box collection
+pub fn upgrade_bear(
+    rpc: &dyn Rpc,
+    payer: &Keypair,
+    bear_mint: &Pubkey,
+    box_mint: &Pubkey,
+    rarity_rate: u64,
+    rarity_proof: &[String],
+) -> CliResult {
+    let box_collection: Pubkey = BOX_COLLECTION_ADDRESS.parse().unwrap();
+    let rarity_proof = rarity_proof
+        .iter()
+        .map(|node| parse_node(node))
+        .collect::<CliResult<Vec<_>>>()?;
+    send(
+        rpc,
+        payer,
+        &[instruction::upgrade_bear(
+            &payer.pubkey(),
+            bear_mint,
+            box_mint,
+            Some(&box_collection),
+            rarity_rate,
+            rarity_proof,
+        )],
+    )
+}
+
+pub fn list_for_rent(
+    rpc: &dyn Rpc,
+    payer: &Keypair,
+    bear_mint: &Pubkey,
+    price: u64,
+    duration: i64,
+    pay_in_medal: bool,
+) -> CliResult {
+    send(
+        rpc,
+        payer,
+        &[instruction::list_for_rent(
+            &payer.pubkey(),
+            bear_mint,
+            price,
+            duration,
+            pay_in_medal,
+        )],
+    )
+}
+
+pub fn cancel_rental(rpc: &dyn Rpc, payer: &Keypair, bear_mint: &Pubkey) -> CliResult {
+    send(
+        rpc,
+        payer,
+        &[instruction::cancel_rental_listing(
+            &payer.pubkey(),
+            bear_mint,
+        )],
+    )
+}
+
+/// Rent a listed bear, paying its last renter first so the new rental can start
+pub fn rent(rpc: &dyn Rpc, payer: &Keypair, bear_mint: &Pubkey, owner: &Pubkey) -> CliResult {
+    let renter = payer.pubkey();
+    let listing = rental_listing(rpc, bear_mint, owner)?;
+    println!(
+        "price: {} {} for {}s",
+        listing.price,
+        if listing.pay_in_medal {
+            "MEDAL base units"
+        } else {
+            "lamports"
+        },
+        listing.duration
+    );
+
+    let mut instructions = vec![];
+    if !listing.is_settled() {
+        instructions.push(create_token_account(
+            &renter,
+            &listing.renter,
+            &pda::medal_mint(),
+        ));
+        instructions.push(instruction::claim_rental_reward(&listing));
+    }
+    if listing.pay_in_medal {
+        instructions.push(create_token_account(&renter, owner, &pda::medal_mint()));
+    }
+    instructions.push(create_token_account(&renter, &renter, &pda::medal_mint()));
+    instructions.push(instruction::rent_nft(&renter, &listing));
+    send(rpc, payer, &instructions)
+}
+
+pub fn claim_rental(
+    rpc: &dyn Rpc,
+    payer: &Keypair,
+    bear_mint: &Pubkey,
+    owner: &Pubkey,
+) -> CliResult {
+    let listing = rental_listing(rpc, bear_mint, owner)?;
+    let global = global_pool(rpc)?;
+    let pending = reward::pending_rental_reward(&listing, &global, now(rpc)?)
+        .map_err(|err| err.to_string())?;
+    println!("renter: {}", listing.renter);
+    println!("rental ends: {}", listing.rent_end);
+    println!("pending: {}", pending);
+    send(
+        rpc,
+        payer,
+        &[
+            create_token_account(&payer.pubkey(), &listing.renter, &pda::medal_mint()),
+            instruction::claim_rental_reward(&listing),
+        ],
+    )
+}
+
+/// Snapshot the signer's voting power, creating its voter weight record on first use. Every bear
+/// staked by the signer is counted and stays locked in its pool for the vote lock.
+pub fn snapshot(rpc: &dyn Rpc, payer: &Keypair) -> CliResult {
+    let owner = payer.pubkey();
+    let stake_records: Vec<Pubkey> = rpc
+        .program_accounts(vec![
+            RpcFilterType::DataSize(StakeRecord::SIZE as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8, owner.as_ref())),
+        ])?
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect();
+    let data = rpc.account_data(&pda::governance_config().0)?;
+    let config = state::decode_governance_config(&data).map_err(|err| err.to_string())?;
+    let address = pda::voter_weight_record(&config.realm, &config.governing_token_mint, &owner).0;
+
+    let mut instructions = vec![];
+    if rpc.account(&address)?.is_none() {
+        instructions.push(instruction::create_voter_weight_record(&owner, &config));
+    }
+    instructions.push(instruction::snapshot(&owner, &config, &stake_records));
+    send(rpc, payer, &instructions)?;
+
+    let data = rpc.account_data(&address)?;
+    let record = state::decode_voter_weight_record(&data).map_err(|err| err.to_string())?;
+    println!("VoterWeightRecord: {}", address);
+    println!("voter_weight: {}", record.voter_weight);
+    println!("slot: {:?}", record.voter_weight_expiry);
+    Ok(())
+}
+
+pub fn show_stake_record(rpc: &dyn Rpc, bear_mint: &Pubkey) -> CliResult {
+    let address = pda::stake_record(bear_mint).0;
+    let account = rpc.account(&address)?;
+    let account = match account {
+        Some(account) => account,
+        None => {
+            println!("{} is not staked", bear_mint);
+            return Ok(());
+        }
+    };
+    let record = state::decode_stake_record(&account.data).map_err(|err| err.to_string())?;
+    println!("Stake record: {}", address);
+    println!("  owner: {}", record.owner);
+    println!("  pool: {}", record.pool_id);
+    println!("  staked since: {}", record.staked_time);
+    println!("  tier: {}", record.tier);
+    if record.box_id != 0 {
+        println!("  box: {} (#{})", record.box_mint, record.box_id);
+    }
+    Ok(())
+}
+
+pub fn backfill_stake_record(
+    rpc: &dyn Rpc,
+    payer: &Keypair,
+    owner: &Pubkey,
+    pool_id: u64,
+    bear_mint: &Pubkey,
+) -> CliResult {
+    let address = pda::stake_record(bear_mint).0;
+    if rpc.account(&address)?.is_some() {
+        return Err(format!("{} already has a stake record", bear_mint).into());
+    }
+    send(
+        rpc,
+        payer,
+        &[instruction::backfill_stake_record(
+            &payer.pubkey(),
+            owner,
+            pool_id,
+            bear_mint,
+        )],
+    )
+}
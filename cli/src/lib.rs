@@ -0,0 +1,7 @@
+//! Commands behind `armory-cli`. They talk to the cluster through [`rpc::Rpc`], which
+//! `RpcClient` implements and the tests implement over a `solana-program-test` bank.
+
+pub mod commands;
+pub mod rpc;
+
+pub type CliResult<T = ()> = Result<T, Box<dyn std::error::Error>>;
@@ -0,0 +1,229 @@
+//! Command line client for Armory Staking.
+//!
+//! The RPC URL and signer come from the Solana CLI config (`--config`, defaulting to
+//! `~/.config/solana/cli/config.yml`) and can be overridden with `--url` and `--keypair`,
+//! so the same commands run against devnet, mainnet or a local `solana-test-validator`.
+
+use anchor_lang::prelude::Pubkey;
+use armory_cli::{commands, CliResult};
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{read_keypair_file, Signer},
+};
+
+#[derive(Parser)]
+#[clap(
+    name = "armory-cli",
+    version,
+    about = "Manage Armory Staking from the command line"
+)]
+struct Cli {
+    /// Solana CLI config file holding the RPC URL and keypair path
+    #[clap(long, global = true)]
+    config: Option<String>,
+
+    /// RPC URL, overriding the config file
+    #[clap(long, short = 'u', global = true)]
+    url: Option<String>,
+
+    /// Signer keypair file, overriding the config file
+    #[clap(long, short = 'k', global = true)]
+    keypair: Option<String>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the global authority, the signer becomes super admin
+    InitGlobal,
+    /// Grow a global pool created before the emission schedule to the current layout
+    MigrateGlobal,
+    /// Allocate and initialize the signer's main pool account
+    InitUser,
+    /// Stake a bear, with an optional box
+    Stake {
+        bear_mint: Pubkey,
+        #[clap(long, requires = "box_id")]
+        box_mint: Option<Pubkey>,
+        #[clap(long, requires = "box_mint")]
+        box_id: Option<u64>,
+        /// Daily rate from the rarity table, required once a rarity root is published
+        #[clap(long, default_value_t = 0)]
+        rarity_rate: u64,
+        /// Merkle proof nodes of the rarity table, hex encoded
+        #[clap(long, multiple_values = true)]
+        rarity_proof: Vec<String>,
+    },
+    /// Unstake a bear and its box
+    Unstake {
+        bear_mint: Pubkey,
+        /// Pay the accrued reward right away instead of parking it for the next claim
+        #[clap(long)]
+        claim: bool,
+    },
+    /// Move a staked bear into a transferable receipt NFT
+    MintReceipt { bear_mint: Pubkey },
+    /// Burn a held receipt NFT for its bear, box and accrued MEDAL
+    UnstakeReceipt { receipt_mint: Pubkey },
+    /// Burn a held box to raise a held bear one tier for its future stakes
+    UpgradeBear {
+        bear_mint: Pubkey,
+        box_mint: Pubkey,
+        /// Daily rate from the rarity table, required once a rarity root is published
+        #[clap(long, default_value_t = 0)]
+        rarity_rate: u64,
+        /// Merkle proof nodes of the rarity table, hex encoded
+        #[clap(long, multiple_values = true)]
+        rarity_proof: Vec<String>,
+    },
+    /// List a staked bear for rent
+    ListForRent {
+        bear_mint: Pubkey,
+        /// Lamports, or MEDAL base units with --medal
+        #[clap(long)]
+        price: u64,
+        /// Rental length in seconds, at most a year
+        #[clap(long)]
+        duration: i64,
+        #[clap(long)]
+        medal: bool,
+    },
+    /// Close the signer's rental listing of a bear
+    CancelRental { bear_mint: Pubkey },
+    /// Rent the reward stream of a listed bear
+    Rent { bear_mint: Pubkey, owner: Pubkey },
+    /// Pay the renter of a listed bear what it accrued so far
+    ClaimRental { bear_mint: Pubkey, owner: Pubkey },
+    /// Claim MEDAL, as the owner or its claim delegate
+    Claim {
+        /// Owner of the user pool, defaults to the signer
+        #[clap(long)]
+        owner: Option<Pubkey>,
+        /// Take what the vault holds and carry the rest
+        #[clap(long)]
+        allow_partial: bool,
+        /// Skip vesting and pay out now with the haircut
+        #[clap(long)]
+        instant: bool,
+        /// Co-sponsored reward token slots to claim as well
+        #[clap(long, multiple_values = true)]
+        reward_token_slots: Vec<u8>,
+    },
+    /// Print the global pool
+    ShowGlobal,
+    /// Print a user pool and its staked NFTs
+    ShowUser { owner: Option<Pubkey> },
+    /// Move MEDAL base units from the signer into the reward vault
+    FundVault { amount: u64 },
+    /// Print the reward owed to a user right now
+    Pending { owner: Option<Pubkey> },
+    /// Print the main pool leaderboard
+    ShowLeaderboard,
+    /// Record the signer's voting power in its SPL Governance voter weight record
+    Snapshot,
+    /// Print who stakes a bear, from its stake record
+    ShowStakeRecord { bear_mint: Pubkey },
+    /// Create the stake record of a bear staked before stake records existed
+    BackfillStakeRecord {
+        bear_mint: Pubkey,
+        /// Owner of the user pool the bear is staked in
+        owner: Pubkey,
+        /// Seasonal pool the bear is staked in, 0 for the main pool
+        #[clap(long, default_value_t = 0)]
+        pool_id: u64,
+    },
+}
+
+fn main() -> CliResult {
+    let cli = Cli::parse();
+
+    let config_file = cli
+        .config
+        .or_else(|| solana_cli_config::CONFIG_FILE.clone())
+        .ok_or("no Solana CLI config file")?;
+    let config = solana_cli_config::Config::load(&config_file).unwrap_or_default();
+    let url = cli.url.unwrap_or(config.json_rpc_url);
+    let keypair_path = cli.keypair.unwrap_or(config.keypair_path);
+
+    let rpc = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| format!("failed to read keypair {}: {}", keypair_path, err))?;
+
+    match cli.command {
+        Command::InitGlobal => commands::init_global(&rpc, &payer),
+        Command::MigrateGlobal => commands::migrate_global(&rpc, &payer),
+        Command::InitUser => commands::init_user(&rpc, &payer),
+        Command::Stake {
+            bear_mint,
+            box_mint,
+            box_id,
+            rarity_rate,
+            rarity_proof,
+        } => commands::stake(
+            &rpc,
+            &payer,
+            &bear_mint,
+            box_mint.zip(box_id),
+            rarity_rate,
+            &rarity_proof,
+        ),
+        Command::Unstake { bear_mint, claim } => commands::unstake(&rpc, &payer, &bear_mint, claim),
+        Command::MintReceipt { bear_mint } => commands::mint_receipt(&rpc, &payer, &bear_mint),
+        Command::UnstakeReceipt { receipt_mint } => {
+            commands::unstake_receipt(&rpc, &payer, &receipt_mint)
+        }
+        Command::UpgradeBear {
+            bear_mint,
+            box_mint,
+            rarity_rate,
+            rarity_proof,
+        } => commands::upgrade_bear(
+            &rpc,
+            &payer,
+            &bear_mint,
+            &box_mint,
+            rarity_rate,
+            &rarity_proof,
+        ),
+        Command::ListForRent {
+            bear_mint,
+            price,
+            duration,
+            medal,
+        } => commands::list_for_rent(&rpc, &payer, &bear_mint, price, duration, medal),
+        Command::CancelRental { bear_mint } => commands::cancel_rental(&rpc, &payer, &bear_mint),
+        Command::Rent { bear_mint, owner } => commands::rent(&rpc, &payer, &bear_mint, &owner),
+        Command::ClaimRental { bear_mint, owner } => {
+            commands::claim_rental(&rpc, &payer, &bear_mint, &owner)
+        }
+        Command::Claim {
+            owner,
+            allow_partial,
+            instant,
+            reward_token_slots,
+        } => commands::claim(
+            &rpc,
+            &payer,
+            owner,
+            allow_partial,
+            instant,
+            &reward_token_slots,
+        ),
+        Command::ShowGlobal => commands::show_global(&rpc),
+        Command::ShowUser { owner } => commands::show_user(&rpc, &owner.unwrap_or(payer.pubkey())),
+        Command::FundVault { amount } => commands::fund_vault(&rpc, &payer, amount),
+        Command::Pending { owner } => commands::pending(&rpc, &owner.unwrap_or(payer.pubkey())),
+        Command::ShowLeaderboard => commands::show_leaderboard(&rpc),
+        Command::Snapshot => commands::snapshot(&rpc, &payer),
+        Command::ShowStakeRecord { bear_mint } => commands::show_stake_record(&rpc, &bear_mint),
+        Command::BackfillStakeRecord {
+            bear_mint,
+            owner,
+            pool_id,
+        } => commands::backfill_stake_record(&rpc, &payer, &owner, pool_id, &bear_mint),
+    }
+}
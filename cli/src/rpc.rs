@@ -0,0 +1,58 @@
+use anchor_lang::prelude::Pubkey;
+use solana_client::{
+    rpc_client::RpcClient, rpc_config::RpcProgramAccountsConfig, rpc_filter::RpcFilterType,
+};
+use solana_sdk::{account::Account, hash::Hash, signature::Signature, transaction::Transaction};
+
+use crate::CliResult;
+
+/// What the commands read from and send to a cluster, so they also run against a test bank
+pub trait Rpc {
+    fn account(&self, address: &Pubkey) -> CliResult<Option<Account>>;
+
+    /// Accounts of the staking program matching all `filters`
+    fn program_accounts(&self, filters: Vec<RpcFilterType>) -> CliResult<Vec<(Pubkey, Account)>>;
+
+    fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> CliResult<u64>;
+
+    fn latest_blockhash(&self) -> CliResult<Hash>;
+
+    fn send_and_confirm(&self, transaction: &Transaction) -> CliResult<Signature>;
+
+    fn account_data(&self, address: &Pubkey) -> CliResult<Vec<u8>> {
+        let account = self
+            .account(address)?
+            .ok_or_else(|| format!("account {} not found", address))?;
+        Ok(account.data)
+    }
+}
+
+impl Rpc for RpcClient {
+    fn account(&self, address: &Pubkey) -> CliResult<Option<Account>> {
+        Ok(self
+            .get_account_with_commitment(address, self.commitment())?
+            .value)
+    }
+
+    fn program_accounts(&self, filters: Vec<RpcFilterType>) -> CliResult<Vec<(Pubkey, Account)>> {
+        Ok(self.get_program_accounts_with_config(
+            &armory_staking::ID,
+            RpcProgramAccountsConfig {
+                filters: Some(filters),
+                ..RpcProgramAccountsConfig::default()
+            },
+        )?)
+    }
+
+    fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> CliResult<u64> {
+        Ok(self.get_minimum_balance_for_rent_exemption(data_len)?)
+    }
+
+    fn latest_blockhash(&self) -> CliResult<Hash> {
+        Ok(self.get_latest_blockhash()?)
+    }
+
+    fn send_and_confirm(&self, transaction: &Transaction) -> CliResult<Signature> {
+        Ok(self.send_and_confirm_transaction_with_spinner(transaction)?)
+    }
+}
@@ -0,0 +1,269 @@
+//! Runs the CLI commands against the program booted in `solana-program-test`, through an
+//! `Rpc` implemented over the test bank.
+//!
+//! Staking only reads Token Metadata accounts, so the bear's metadata with a verified creator
+//! is injected directly, like the program's own integration tests do.
+
+use std::cell::RefCell;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use armory_cli::{commands, rpc::Rpc, CliResult};
+use armory_staking::{account::StakeRecord, constant::*};
+use armory_staking_client::{instruction, pda, state};
+use mpl_token_metadata::state::{Creator, Data, Key, Metadata, MAX_METADATA_LEN};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_program_test::{processor, BanksClient, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::Instruction,
+    program_option::COption,
+    program_pack::Pack,
+    signature::{Keypair, Signature, Signer},
+    system_program,
+    transaction::Transaction,
+};
+use tokio::runtime::Runtime;
+
+const VAULT_FUNDING: u64 = 1_000_000_000_000;
+
+/// `Rpc` over a program test bank. The bank cannot scan accounts, so `program_accounts`
+/// applies the filters to the addresses the test watches.
+struct BankRpc {
+    runtime: Runtime,
+    banks: RefCell<BanksClient>,
+    watched: RefCell<Vec<Pubkey>>,
+}
+
+impl BankRpc {
+    fn start(program_test: ProgramTest) -> Self {
+        let runtime = Runtime::new().unwrap();
+        let (banks, _, _) = runtime.block_on(program_test.start());
+        BankRpc {
+            runtime,
+            banks: RefCell::new(banks),
+            watched: RefCell::default(),
+        }
+    }
+
+    fn watch(&self, address: Pubkey) {
+        self.watched.borrow_mut().push(address);
+    }
+}
+
+impl Rpc for BankRpc {
+    fn account(&self, address: &Pubkey) -> CliResult<Option<Account>> {
+        let mut banks = self.banks.borrow_mut();
+        Ok(self.runtime.block_on(banks.get_account(*address))?)
+    }
+
+    fn program_accounts(&self, filters: Vec<RpcFilterType>) -> CliResult<Vec<(Pubkey, Account)>> {
+        let mut accounts = vec![];
+        for address in self.watched.borrow().iter() {
+            let account = match self.account(address)? {
+                Some(account) if account.owner == armory_staking::ID => account,
+                _ => continue,
+            };
+            let matches = filters.iter().all(|filter| match filter {
+                RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+                RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&account.data),
+                _ => false,
+            });
+            if matches {
+                accounts.push((*address, account));
+            }
+        }
+        Ok(accounts)
+    }
+
+    fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> CliResult<u64> {
+        let mut banks = self.banks.borrow_mut();
+        let rent = self.runtime.block_on(banks.get_rent())?;
+        Ok(rent.minimum_balance(data_len))
+    }
+
+    fn latest_blockhash(&self) -> CliResult<Hash> {
+        let mut banks = self.banks.borrow_mut();
+        Ok(self.runtime.block_on(banks.get_latest_blockhash())?)
+    }
+
+    fn send_and_confirm(&self, transaction: &Transaction) -> CliResult<Signature> {
+        let mut banks = self.banks.borrow_mut();
+        self.runtime
+            .block_on(banks.process_transaction(transaction.clone()))?;
+        Ok(transaction.signatures[0])
+    }
+}
+
+fn add_account(program_test: &mut ProgramTest, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+fn add_mint(program_test: &mut ProgramTest, mint: Pubkey, authority: &Pubkey, decimals: u8) {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(*authority),
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    add_account(program_test, mint, spl_token::id(), data);
+}
+
+/// Associated token account of `owner` holding `amount` of `mint`
+fn add_token_account(program_test: &mut ProgramTest, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    add_account(
+        program_test,
+        pda::token_account(owner, mint),
+        spl_token::id(),
+        data,
+    );
+}
+
+/// Mint a mock bear held by `owner`, with metadata verified by the bear collection
+fn add_bear(program_test: &mut ProgramTest, owner: &Pubkey, name: &str) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    add_mint(program_test, mint, owner, 0);
+
+    let mut data = vec![0; MAX_METADATA_LEN];
+    Metadata {
+        key: Key::MetadataV1,
+        update_authority: *owner,
+        mint,
+        data: Data {
+            name: format!("{:\0<32}", name),
+            symbol: String::new(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: Some(vec![Creator {
+                address: BEAR_COLLECTION_ADDRESS.parse().unwrap(),
+                verified: true,
+                share: 100,
+            }]),
+        },
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: None,
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    }
+    .save(&mut data)
+    .unwrap();
+    add_account(
+        program_test,
+        pda::metadata(&mint),
+        mpl_token_metadata::id(),
+        data,
+    );
+    add_token_account(program_test, &mint, owner, 1);
+    mint
+}
+
+fn send(rpc: &BankRpc, payer: &Keypair, instruction: Instruction) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        rpc.latest_blockhash().unwrap(),
+    );
+    rpc.send_and_confirm(&transaction).unwrap();
+}
+
+#[test]
+fn init_stake_and_snapshot() {
+    let mut program_test = ProgramTest::new(
+        "armory_staking",
+        armory_staking::ID,
+        processor!(armory_staking::entry),
+    );
+    let owner = Keypair::new();
+    add_account(
+        &mut program_test,
+        owner.pubkey(),
+        system_program::id(),
+        vec![],
+    );
+    add_mint(&mut program_test, pda::medal_mint(), &owner.pubkey(), 9);
+    add_token_account(
+        &mut program_test,
+        &pda::medal_mint(),
+        &pda::global_authority().0,
+        VAULT_FUNDING,
+    );
+    let bear_mint = add_bear(&mut program_test, &owner.pubkey(), "Bear #100");
+
+    let rpc = BankRpc::start(program_test);
+    commands::init_global(&rpc, &owner).unwrap();
+    commands::stake(&rpc, &owner, &bear_mint, None, 0, &[]).unwrap();
+
+    let stake_record = pda::stake_record(&bear_mint).0;
+    let record = state::decode_stake_record(&rpc.account_data(&stake_record).unwrap()).unwrap();
+    assert_eq!(record.owner, owner.pubkey());
+    assert_eq!(record.nft_mint, bear_mint);
+    assert_eq!(record.vote_lock_until, 0);
+
+    let realm = Pubkey::new_unique();
+    let governing_token_mint = Pubkey::new_unique();
+    send(
+        &rpc,
+        &owner,
+        instruction::init_governance(
+            &owner.pubkey(),
+            &realm,
+            &governing_token_mint,
+            0,
+            0,
+            ONE_DAY,
+        ),
+    );
+
+    // The snapshot finds the stake record among other program accounts by size and owner
+    rpc.watch(pda::global_authority().0);
+    rpc.watch(pda::user_pool(&owner.pubkey(), 0));
+    rpc.watch(pda::governance_config().0);
+    rpc.watch(stake_record);
+    commands::snapshot(&rpc, &owner).unwrap();
+
+    let voter_weight_record =
+        pda::voter_weight_record(&realm, &governing_token_mint, &owner.pubkey()).0;
+    let voter_weight_record =
+        state::decode_voter_weight_record(&rpc.account_data(&voter_weight_record).unwrap())
+            .unwrap();
+    assert_eq!(voter_weight_record.governing_token_owner, owner.pubkey());
+    assert_eq!(voter_weight_record.voter_weight, record.reward_rate);
+    assert!(voter_weight_record.voter_weight > 0);
+
+    let record = state::decode_stake_record(&rpc.account_data(&stake_record).unwrap()).unwrap();
+    assert!(record.vote_lock_until > 0);
+    assert_eq!(
+        rpc.account_data(&stake_record).unwrap().len(),
+        StakeRecord::SIZE
+    );
+}
@@ -0,0 +1,950 @@
+//! One builder per program instruction. Accounts are derived the way `cli/script.ts` did:
+//! NFTs and rewards move between associated token accounts, escrows belong to the
+//! global authority for the main pool and to the pool PDA for seasonal pools.
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_instruction, system_program, sysvar},
+    InstructionData,
+};
+use anchor_spl::{associated_token, token::spl_token};
+use armory_staking::{
+    account::{EmissionSchedule, GovernanceConfig, RentalListing, SetBonus, StakeReceipt},
+    accounts, instruction as ix,
+};
+use mpl_bubblegum::state::metaplex_adapter::MetadataArgs;
+use spl_account_compression::Noop;
+
+use crate::pda;
+use crate::state::USER_POOL_SPACE;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: armory_staking::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize(admin: &Pubkey) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    build(
+        accounts::Initialize {
+            admin: *admin,
+            global_authority,
+            sol_vault: pda::sol_vault().0,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        ix::Initialize { _global_bump: bump },
+    )
+}
+
+/// Grow a global pool created before the emission schedule to the current layout
+pub fn migrate_global_pool(admin: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateGlobalPool {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            system_program: system_program::ID,
+        },
+        ix::MigrateGlobalPool {},
+    )
+}
+
+pub fn set_emission_schedule(admin: &Pubkey, emission: EmissionSchedule) -> Instruction {
+    build(
+        accounts::SetEmissionSchedule {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+        },
+        ix::SetEmissionSchedule {
+            start_time: emission.start_time,
+            epoch_length: emission.epoch_length,
+            decay_bps: emission.decay_bps,
+        },
+    )
+}
+
+pub fn set_rarity_root(admin: &Pubkey, rarity_root: [u8; 32]) -> Instruction {
+    build(
+        accounts::SetRarityRoot {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+        },
+        ix::SetRarityRoot { rarity_root },
+    )
+}
+
+pub fn set_vesting_config(
+    admin: &Pubkey,
+    vesting_duration: i64,
+    instant_haircut_bps: u64,
+) -> Instruction {
+    build(
+        accounts::SetVestingConfig {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+        },
+        ix::SetVestingConfig {
+            vesting_duration,
+            instant_haircut_bps,
+        },
+    )
+}
+
+pub fn set_fees(admin: &Pubkey, stake_fee: u64, unstake_fee: u64, claim_fee: u64) -> Instruction {
+    build(
+        accounts::SetFees {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+        },
+        ix::SetFees {
+            stake_fee,
+            unstake_fee,
+            claim_fee,
+        },
+    )
+}
+
+pub fn withdraw_sol_fees(admin: &Pubkey, amount: u64) -> Instruction {
+    let (sol_vault, bump) = pda::sol_vault();
+    build(
+        accounts::WithdrawSolFees {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            sol_vault,
+            system_program: system_program::ID,
+        },
+        ix::WithdrawSolFees {
+            _vault_bump: bump,
+            amount,
+        },
+    )
+}
+
+pub fn add_reward_token(admin: &Pubkey, reward_mint: &Pubkey, rate_per_medal: u64) -> Instruction {
+    build(
+        accounts::AddRewardToken {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            reward_mint: *reward_mint,
+        },
+        ix::AddRewardToken { rate_per_medal },
+    )
+}
+
+pub fn set_reward_token_rate(admin: &Pubkey, slot: u8, rate_per_medal: u64) -> Instruction {
+    build(
+        accounts::SetRewardTokenRate {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+        },
+        ix::SetRewardTokenRate {
+            slot,
+            rate_per_medal,
+        },
+    )
+}
+
+pub fn init_governance(
+    admin: &Pubkey,
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    duration_bonus_bps: u64,
+    duration_cap: i64,
+    vote_lock: i64,
+) -> Instruction {
+    build(
+        accounts::InitGovernance {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            governance_config: pda::governance_config().0,
+            system_program: system_program::ID,
+        },
+        ix::InitGovernance {
+            realm: *realm,
+            governing_token_mint: *governing_token_mint,
+            duration_bonus_bps,
+            duration_cap,
+            vote_lock,
+        },
+    )
+}
+
+pub fn set_voting_weights(
+    admin: &Pubkey,
+    duration_bonus_bps: u64,
+    duration_cap: i64,
+    vote_lock: i64,
+) -> Instruction {
+    build(
+        accounts::SetVotingWeights {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            governance_config: pda::governance_config().0,
+        },
+        ix::SetVotingWeights {
+            duration_bonus_bps,
+            duration_cap,
+            vote_lock,
+        },
+    )
+}
+
+pub fn init_set_bonus_config(admin: &Pubkey) -> Instruction {
+    build(
+        accounts::InitSetBonusConfig {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            set_bonus_config: pda::set_bonus_config().0,
+            system_program: system_program::ID,
+        },
+        ix::InitSetBonusConfig {},
+    )
+}
+
+pub fn set_set_bonus(admin: &Pubkey, slot: u8, set_bonus: SetBonus) -> Instruction {
+    build(
+        accounts::SetSetBonus {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            set_bonus_config: pda::set_bonus_config().0,
+        },
+        ix::SetSetBonus {
+            slot,
+            tier_mask: set_bonus.tier_mask,
+            min_pairs: set_bonus.min_pairs,
+            bonus_bps: set_bonus.bonus_bps,
+        },
+    )
+}
+
+/// Allocate the user account of `pool_id`, to be followed by `init_user_pool` or `init_pool_user`
+pub fn create_user_pool(owner: &Pubkey, pool_id: u64, lamports: u64) -> Instruction {
+    system_instruction::create_account_with_seed(
+        owner,
+        &pda::user_pool(owner, pool_id),
+        owner,
+        &pda::user_pool_seed(pool_id),
+        lamports,
+        USER_POOL_SPACE as u64,
+        &armory_staking::ID,
+    )
+}
+
+pub fn init_user_pool(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::InitUserPool {
+            user_pool: pda::user_pool(owner, 0),
+            owner: *owner,
+        },
+        ix::InitUserPool {},
+    )
+}
+
+pub fn migrate_user_pool(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateUserPool {
+            owner: *owner,
+            user_pool: pda::user_pool(owner, 0),
+            system_program: system_program::ID,
+        },
+        ix::MigrateUserPool {},
+    )
+}
+
+/// Create the vesting schedule of `owner`, required before its first vested claim
+pub fn init_vesting(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::InitVesting {
+            owner: *owner,
+            vesting: pda::vesting(owner).0,
+            system_program: system_program::ID,
+        },
+        ix::InitVesting {},
+    )
+}
+
+pub fn set_claim_config(
+    owner: &Pubkey,
+    claim_delegate: &Pubkey,
+    reward_destination: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::SetClaimConfig {
+            owner: *owner,
+            user_pool: pda::user_pool(owner, 0),
+        },
+        ix::SetClaimConfig {
+            claim_delegate: *claim_delegate,
+            reward_destination: *reward_destination,
+        },
+    )
+}
+
+/// Stake a bear in the main pool, with an optional `(box mint, box id)`
+pub fn stake_nft(
+    owner: &Pubkey,
+    bear_mint: &Pubkey,
+    bear_box: Option<(Pubkey, u64)>,
+    rarity_rate: u64,
+    rarity_proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    let (box_mint, box_id) = bear_box.unwrap_or((*bear_mint, 0));
+    build(
+        accounts::StakeNft {
+            owner: *owner,
+            global_authority,
+            user_pool: pda::user_pool(owner, 0),
+            nft_mint: *bear_mint,
+            nft_box_mint: box_mint,
+            user_bear_account: pda::token_account(owner, bear_mint),
+            dest_bear_account: pda::token_account(&global_authority, bear_mint),
+            user_box_account: pda::token_account(owner, &box_mint),
+            dest_box_account: pda::token_account(&global_authority, &box_mint),
+            reward_vault: pda::reward_vault(),
+            user_reward_account: pda::token_account(owner, &pda::medal_mint()),
+            mint_metadata: pda::metadata(bear_mint),
+            bear_upgrade: pda::bear_upgrade(bear_mint).0,
+            stake_record: pda::stake_record(bear_mint).0,
+            set_bonus_config: pda::set_bonus_config().0,
+            sol_vault: pda::sol_vault().0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        ix::StakeNft {
+            _global_bump: bump,
+            _box_id: box_id,
+            rarity_rate,
+            rarity_proof,
+        },
+    )
+}
+
+/// Unstake a bear from the main pool. Passing a reward destination pays accrued MEDAL
+/// right away when vesting is off, otherwise rewards stay pending until claimed.
+pub fn unstake_nft(
+    owner: &Pubkey,
+    bear_mint: &Pubkey,
+    bear_box: Option<(Pubkey, u64)>,
+    reward_destination: Option<&Pubkey>,
+) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    let (box_mint, box_id) = bear_box.unwrap_or((*bear_mint, 0));
+    let mut instruction = build(
+        accounts::UnstakeNft {
+            owner: *owner,
+            user_pool: pda::user_pool(owner, 0),
+            global_authority,
+            nft_mint: *bear_mint,
+            nft_box_mint: box_mint,
+            user_bear_account: pda::token_account(owner, bear_mint),
+            dest_bear_account: pda::token_account(&global_authority, bear_mint),
+            user_box_account: pda::token_account(owner, &box_mint),
+            dest_box_account: pda::token_account(&global_authority, &box_mint),
+            leaderboard: pda::leaderboard(0).0,
+            stake_record: pda::stake_record(bear_mint).0,
+            set_bonus_config: pda::set_bonus_config().0,
+            sol_vault: pda::sol_vault().0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::UnstakeNft {
+            _global_bump: bump,
+            _box_id: box_id,
+        },
+    );
+    if let Some(destination) = reward_destination {
+        instruction.accounts.extend([
+            AccountMeta::new(pda::reward_vault(), false),
+            AccountMeta::new(pda::token_account(destination, &pda::medal_mint()), false),
+        ]);
+    }
+    instruction
+}
+
+/// Claim MEDAL for `owner`'s main pool, signed by the owner or its claim delegate.
+/// `reward_tokens` lists the co-sponsored `(slot, mint)` pairs to pay out as well.
+/// A vested claim needs the owner's schedule from `init_vesting` to exist.
+pub fn claim_reward(
+    claimer: &Pubkey,
+    owner: &Pubkey,
+    reward_destination: &Pubkey,
+    allow_partial: bool,
+    instant: bool,
+    reward_tokens: &[(u8, Pubkey)],
+) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    let mut instruction = build(
+        accounts::ClaimReward {
+            claimer: *claimer,
+            user_pool: pda::user_pool(owner, 0),
+            global_authority,
+            reward_vault: pda::reward_vault(),
+            user_reward_account: pda::token_account(reward_destination, &pda::medal_mint()),
+            vesting: pda::vesting(owner).0,
+            leaderboard: pda::leaderboard(0).0,
+            sol_vault: pda::sol_vault().0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::ClaimReward {
+            _global_bump: bump,
+            allow_partial,
+            instant,
+            reward_token_slots: reward_tokens.iter().map(|(slot, _)| *slot).collect(),
+        },
+    );
+    for (_, mint) in reward_tokens {
+        instruction.accounts.extend([
+            AccountMeta::new(pda::token_account(&global_authority, mint), false),
+            AccountMeta::new(pda::token_account(reward_destination, mint), false),
+        ]);
+    }
+    instruction
+}
+
+/// Leaf of a compressed NFT as the DAS `getAsset` and `getAssetProof` calls return it
+pub struct LeafProof {
+    pub merkle_tree: Pubkey,
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+    /// Proof nodes, without the ones the tree keeps in its canopy
+    pub proof: Vec<Pubkey>,
+}
+
+impl LeafProof {
+    fn append_proof(&self, instruction: &mut Instruction) {
+        instruction.accounts.extend(
+            self.proof
+                .iter()
+                .map(|node| AccountMeta::new_readonly(*node, false)),
+        );
+    }
+}
+
+/// Stake a compressed bear owned by `owner` in the main pool
+pub fn stake_cnft(
+    owner: &Pubkey,
+    leaf: &LeafProof,
+    metadata: MetadataArgs,
+    rarity_rate: u64,
+    rarity_proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let asset_id = pda::asset_id(&leaf.merkle_tree, leaf.nonce);
+    let (global_authority, bump) = pda::global_authority();
+    let mut instruction = build(
+        accounts::StakeCnft {
+            owner: *owner,
+            global_authority,
+            user_pool: pda::user_pool(owner, 0),
+            leaf_delegate: *owner,
+            tree_authority: pda::tree_authority(&leaf.merkle_tree),
+            merkle_tree: leaf.merkle_tree,
+            stake_record: pda::stake_record(&asset_id).0,
+            set_bonus_config: pda::set_bonus_config().0,
+            bear_upgrade: pda::bear_upgrade(&asset_id).0,
+            sol_vault: pda::sol_vault().0,
+            bubblegum_program: mpl_bubblegum::ID,
+            compression_program: spl_account_compression::ID,
+            log_wrapper: Noop::id(),
+            system_program: system_program::ID,
+        },
+        ix::StakeCnft {
+            _global_bump: bump,
+            asset_id,
+            root: leaf.root,
+            metadata,
+            nonce: leaf.nonce,
+            index: leaf.index,
+            rarity_rate,
+            rarity_proof,
+        },
+    );
+    leaf.append_proof(&mut instruction);
+    instruction
+}
+
+/// Unstake a compressed bear, `leaf` is its current leaf now owned by the global authority
+pub fn unstake_cnft(owner: &Pubkey, leaf: &LeafProof) -> Instruction {
+    let asset_id = pda::asset_id(&leaf.merkle_tree, leaf.nonce);
+    let (global_authority, bump) = pda::global_authority();
+    let mut instruction = build(
+        accounts::UnstakeCnft {
+            owner: *owner,
+            user_pool: pda::user_pool(owner, 0),
+            global_authority,
+            tree_authority: pda::tree_authority(&leaf.merkle_tree),
+            merkle_tree: leaf.merkle_tree,
+            leaderboard: pda::leaderboard(0).0,
+            stake_record: pda::stake_record(&asset_id).0,
+            set_bonus_config: pda::set_bonus_config().0,
+            sol_vault: pda::sol_vault().0,
+            bubblegum_program: mpl_bubblegum::ID,
+            compression_program: spl_account_compression::ID,
+            log_wrapper: Noop::id(),
+            system_program: system_program::ID,
+        },
+        ix::UnstakeCnft {
+            _global_bump: bump,
+            asset_id,
+            root: leaf.root,
+            data_hash: leaf.data_hash,
+            creator_hash: leaf.creator_hash,
+            nonce: leaf.nonce,
+            index: leaf.index,
+        },
+    );
+    leaf.append_proof(&mut instruction);
+    instruction
+}
+
+/// Read-only, simulate it and decode the return data with `state::decode_streak_view`
+pub fn view_streak(owner: &Pubkey, pool_id: u64, bear_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::ViewUserPool {
+            user_pool: pda::user_pool(owner, pool_id),
+        },
+        ix::ViewStreak {
+            bear_mint: *bear_mint,
+        },
+    )
+}
+
+/// Move a staked bear into a receipt NFT minted to `owner`, from then on it only accrues MEDAL.
+/// `receipt_mint` is a fresh keypair that must sign the transaction.
+pub fn mint_receipt(owner: &Pubkey, bear_mint: &Pubkey, receipt_mint: &Pubkey) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    build(
+        accounts::MintReceipt {
+            owner: *owner,
+            global_authority,
+            user_pool: pda::user_pool(owner, 0),
+            nft_mint: *bear_mint,
+            mint_metadata: pda::metadata(bear_mint),
+            receipt: pda::receipt(receipt_mint).0,
+            receipt_mint: *receipt_mint,
+            receipt_account: pda::token_account(owner, receipt_mint),
+            receipt_metadata: pda::metadata(receipt_mint),
+            stake_record: pda::stake_record(bear_mint).0,
+            leaderboard: pda::leaderboard(0).0,
+            set_bonus_config: pda::set_bonus_config().0,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        ix::MintReceipt { _global_bump: bump },
+    )
+}
+
+/// Redeem a receipt NFT held by `holder` for its bear, box and accrued MEDAL
+pub fn unstake_receipt(
+    holder: &Pubkey,
+    receipt_mint: &Pubkey,
+    receipt: &StakeReceipt,
+) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    build(
+        accounts::UnstakeReceipt {
+            holder: *holder,
+            global_authority,
+            receipt: pda::receipt(receipt_mint).0,
+            receipt_mint: *receipt_mint,
+            receipt_account: pda::token_account(holder, receipt_mint),
+            nft_mint: receipt.bear_mint,
+            user_bear_account: pda::token_account(holder, &receipt.bear_mint),
+            dest_bear_account: pda::token_account(&global_authority, &receipt.bear_mint),
+            user_box_account: pda::token_account(holder, &receipt.box_mint),
+            dest_box_account: pda::token_account(&global_authority, &receipt.box_mint),
+            reward_vault: pda::reward_vault(),
+            user_reward_account: pda::token_account(holder, &pda::medal_mint()),
+            stake_record: pda::stake_record(&receipt.bear_mint).0,
+            sol_vault: pda::sol_vault().0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::UnstakeReceipt { _global_bump: bump },
+    )
+}
+
+pub fn create_voter_weight_record(owner: &Pubkey, config: &GovernanceConfig) -> Instruction {
+    build(
+        accounts::CreateVoterWeightRecord {
+            owner: *owner,
+            governance_config: pda::governance_config().0,
+            voter_weight_record: pda::voter_weight_record(
+                &config.realm,
+                &config.governing_token_mint,
+                owner,
+            )
+            .0,
+            system_program: system_program::ID,
+        },
+        ix::CreateVoterWeightRecord {},
+    )
+}
+
+/// Refresh the voter weight of `owner` from the stake records of its bears, to be sent in the
+/// same slot as the governance instruction. The counted bears stay locked for the vote lock.
+pub fn snapshot(
+    owner: &Pubkey,
+    config: &GovernanceConfig,
+    stake_records: &[Pubkey],
+) -> Instruction {
+    let mut instruction = build(
+        accounts::Snapshot {
+            owner: *owner,
+            governance_config: pda::governance_config().0,
+            voter_weight_record: pda::voter_weight_record(
+                &config.realm,
+                &config.governing_token_mint,
+                owner,
+            )
+            .0,
+        },
+        ix::Snapshot {},
+    );
+    let mut stake_records = stake_records.to_vec();
+    stake_records.sort();
+    stake_records.dedup();
+    instruction.accounts.extend(
+        stake_records
+            .into_iter()
+            .map(|stake_record| AccountMeta::new(stake_record, false)),
+    );
+    instruction
+}
+
+/// Read-only, simulate it or call it through CPI, the return data is a borsh `bool`
+pub fn is_staked(bear_mint: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(
+        accounts::IsStaked {
+            nft_mint: *bear_mint,
+            stake_record: pda::stake_record(bear_mint).0,
+        },
+        ix::IsStaked { owner: *owner },
+    )
+}
+
+/// Create the missing stake record of a bear `owner` staked in `pool_id` before records existed
+pub fn backfill_stake_record(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    pool_id: u64,
+    bear_mint: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::BackfillStakeRecord {
+            payer: *payer,
+            user_pool: pda::user_pool(owner, pool_id),
+            nft_mint: *bear_mint,
+            stake_record: pda::stake_record(bear_mint).0,
+            system_program: system_program::ID,
+        },
+        ix::BackfillStakeRecord {},
+    )
+}
+
+/// Burn a box held by `owner` to raise a bear one tier, `box_collection` is the collection of the box
+pub fn upgrade_bear(
+    owner: &Pubkey,
+    bear_mint: &Pubkey,
+    box_mint: &Pubkey,
+    box_collection: Option<&Pubkey>,
+    rarity_rate: u64,
+    rarity_proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let mut instruction = build(
+        accounts::UpgradeBear {
+            owner: *owner,
+            global_authority: pda::global_authority().0,
+            bear_mint: *bear_mint,
+            user_bear_account: pda::token_account(owner, bear_mint),
+            bear_metadata: pda::metadata(bear_mint),
+            box_mint: *box_mint,
+            user_box_account: pda::token_account(owner, box_mint),
+            box_metadata: pda::metadata(box_mint),
+            box_edition: pda::edition(box_mint),
+            bear_upgrade: pda::bear_upgrade(bear_mint).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            token_metadata_program: mpl_token_metadata::ID,
+        },
+        ix::UpgradeBear {
+            rarity_rate,
+            rarity_proof,
+        },
+    );
+    if let Some(collection) = box_collection {
+        instruction
+            .accounts
+            .push(AccountMeta::new(pda::metadata(collection), false));
+    }
+    instruction
+}
+
+pub fn list_for_rent(
+    owner: &Pubkey,
+    bear_mint: &Pubkey,
+    price: u64,
+    duration: i64,
+    pay_in_medal: bool,
+) -> Instruction {
+    build(
+        accounts::ListForRent {
+            owner: *owner,
+            user_pool: pda::user_pool(owner, 0),
+            nft_mint: *bear_mint,
+            listing: pda::rental_listing(bear_mint, owner).0,
+            system_program: system_program::ID,
+        },
+        ix::ListForRent {
+            price,
+            duration,
+            pay_in_medal,
+        },
+    )
+}
+
+pub fn cancel_rental_listing(owner: &Pubkey, bear_mint: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelRentalListing {
+            owner: *owner,
+            listing: pda::rental_listing(bear_mint, owner).0,
+        },
+        ix::CancelRentalListing {},
+    )
+}
+
+/// Rent a listed bear, paying in MEDAL between associated token accounts when the listing asks for it
+pub fn rent_nft(renter: &Pubkey, listing: &RentalListing) -> Instruction {
+    let mut instruction = build(
+        accounts::RentNft {
+            renter: *renter,
+            owner: listing.owner,
+            global_authority: pda::global_authority().0,
+            listing: pda::rental_listing(&listing.nft_mint, &listing.owner).0,
+            user_pool: pda::user_pool(&listing.owner, 0),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::RentNft {},
+    );
+    if listing.pay_in_medal {
+        instruction.accounts.extend([
+            AccountMeta::new(pda::token_account(renter, &pda::medal_mint()), false),
+            AccountMeta::new(
+                pda::token_account(&listing.owner, &pda::medal_mint()),
+                false,
+            ),
+        ]);
+    }
+    instruction
+}
+
+/// Pay the renter of a listing, signed by anyone
+pub fn claim_rental_reward(listing: &RentalListing) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    build(
+        accounts::ClaimRentalReward {
+            global_authority,
+            listing: pda::rental_listing(&listing.nft_mint, &listing.owner).0,
+            reward_vault: pda::reward_vault(),
+            renter_reward_account: pda::token_account(&listing.renter, &pda::medal_mint()),
+            token_program: spl_token::ID,
+        },
+        ix::ClaimRentalReward { _global_bump: bump },
+    )
+}
+
+pub fn withdraw_vested(
+    claimer: &Pubkey,
+    owner: &Pubkey,
+    reward_destination: &Pubkey,
+) -> Instruction {
+    let (global_authority, bump) = pda::global_authority();
+    build(
+        accounts::WithdrawVested {
+            claimer: *claimer,
+            user_pool: pda::user_pool(owner, 0),
+            vesting: pda::vesting(owner).0,
+            global_authority,
+            reward_vault: pda::reward_vault(),
+            user_reward_account: pda::token_account(reward_destination, &pda::medal_mint()),
+            token_program: spl_token::ID,
+        },
+        ix::WithdrawVested { _global_bump: bump },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_pool(
+    admin: &Pubkey,
+    pool_id: u64,
+    pool_admin: &Pubkey,
+    bear_collection: &Pubkey,
+    box_collection: &Pubkey,
+    reward_mint: &Pubkey,
+    start_time: i64,
+    end_time: i64,
+) -> Instruction {
+    build(
+        accounts::CreatePool {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            pool: pda::pool(pool_id).0,
+            reward_mint: *reward_mint,
+            system_program: system_program::ID,
+        },
+        ix::CreatePool {
+            pool_id,
+            pool_admin: *pool_admin,
+            bear_collection: *bear_collection,
+            box_collection: *box_collection,
+            start_time,
+            end_time,
+        },
+    )
+}
+
+pub fn update_pool(
+    admin: &Pubkey,
+    pool_id: u64,
+    rarity_root: [u8; 32],
+    emission: EmissionSchedule,
+    start_time: i64,
+    end_time: i64,
+) -> Instruction {
+    build(
+        accounts::UpdatePool {
+            admin: *admin,
+            pool: pda::pool(pool_id).0,
+        },
+        ix::UpdatePool {
+            rarity_root,
+            emission,
+            start_time,
+            end_time,
+        },
+    )
+}
+
+/// Create the leaderboard of a pool, 0 for the main pool
+pub fn create_leaderboard(admin: &Pubkey, pool_id: u64) -> Instruction {
+    build(
+        accounts::CreateLeaderboard {
+            admin: *admin,
+            global_authority: pda::global_authority().0,
+            leaderboard: pda::leaderboard(pool_id).0,
+            system_program: system_program::ID,
+        },
+        ix::CreateLeaderboard { pool_id },
+    )
+}
+
+pub fn init_pool_user(owner: &Pubkey, pool_id: u64) -> Instruction {
+    build(
+        accounts::InitPoolUser {
+            user_pool: pda::user_pool(owner, pool_id),
+            owner: *owner,
+            pool: pda::pool(pool_id).0,
+        },
+        ix::InitPoolUser {},
+    )
+}
+
+/// Stake a bear in a seasonal pool, the box is read from its own metadata
+pub fn stake_pool_nft(
+    owner: &Pubkey,
+    pool_id: u64,
+    bear_mint: &Pubkey,
+    box_mint: Option<&Pubkey>,
+    rarity_rate: u64,
+    rarity_proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let pool = pda::pool(pool_id).0;
+    let mut instruction = build(
+        accounts::StakePoolNft {
+            owner: *owner,
+            global_authority: pda::global_authority().0,
+            pool,
+            user_pool: pda::user_pool(owner, pool_id),
+            nft_mint: *bear_mint,
+            nft_box_mint: *box_mint.unwrap_or(bear_mint),
+            user_bear_account: pda::token_account(owner, bear_mint),
+            dest_bear_account: pda::token_account(&pool, bear_mint),
+            mint_metadata: pda::metadata(bear_mint),
+            bear_upgrade: pda::bear_upgrade(bear_mint).0,
+            stake_record: pda::stake_record(bear_mint).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ix::StakePoolNft {
+            rarity_rate,
+            rarity_proof,
+        },
+    );
+    if let Some(box_mint) = box_mint {
+        instruction.accounts.extend([
+            AccountMeta::new_readonly(pda::metadata(box_mint), false),
+            AccountMeta::new(pda::token_account(owner, box_mint), false),
+            AccountMeta::new(pda::token_account(&pool, box_mint), false),
+        ]);
+    }
+    instruction
+}
+
+pub fn unstake_pool_nft(
+    owner: &Pubkey,
+    pool_id: u64,
+    bear_mint: &Pubkey,
+    box_mint: Option<&Pubkey>,
+) -> Instruction {
+    let (pool, bump) = pda::pool(pool_id);
+    let mut instruction = build(
+        accounts::UnstakePoolNft {
+            owner: *owner,
+            global_authority: pda::global_authority().0,
+            pool,
+            user_pool: pda::user_pool(owner, pool_id),
+            nft_mint: *bear_mint,
+            user_bear_account: pda::token_account(owner, bear_mint),
+            dest_bear_account: pda::token_account(&pool, bear_mint),
+            stake_record: pda::stake_record(bear_mint).0,
+            leaderboard: pda::leaderboard(pool_id).0,
+            token_program: spl_token::ID,
+        },
+        ix::UnstakePoolNft { _pool_bump: bump },
+    );
+    if let Some(box_mint) = box_mint {
+        instruction.accounts.extend([
+            AccountMeta::new(pda::token_account(owner, box_mint), false),
+            AccountMeta::new(pda::token_account(&pool, box_mint), false),
+        ]);
+    }
+    instruction
+}
+
+pub fn claim_pool_reward(owner: &Pubkey, pool_id: u64, reward_mint: &Pubkey) -> Instruction {
+    let (pool, bump) = pda::pool(pool_id);
+    build(
+        accounts::ClaimPoolReward {
+            owner: *owner,
+            pool,
+            user_pool: pda::user_pool(owner, pool_id),
+            reward_vault: pda::token_account(&pool, reward_mint),
+            user_reward_account: pda::token_account(owner, reward_mint),
+            leaderboard: pda::leaderboard(pool_id).0,
+            token_program: spl_token::ID,
+        },
+        ix::ClaimPoolReward { _pool_bump: bump },
+    )
+}
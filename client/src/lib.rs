@@ -0,0 +1,12 @@
+//! Off-chain helpers for Armory Staking: typed instruction builders, PDA derivation,
+//! account decoders and a pending reward calculator sharing the program's reward module.
+
+// Errors are the program's own `anchor_lang::error::Error`
+#![allow(clippy::result_large_err)]
+
+pub mod instruction;
+pub mod pda;
+pub mod reward;
+pub mod state;
+
+pub use armory_staking::{account, constant, ID};
@@ -0,0 +1,154 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use armory_staking::constant::*;
+
+/// Seed of the main pool user account, created with `create_account_with_seed`
+pub const USER_POOL_SEED: &str = "user-pool";
+
+pub fn global_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_AUTHORITY_SEED.as_bytes()], &armory_staking::ID)
+}
+
+pub fn sol_vault() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SOL_VAULT_SEED.as_bytes()], &armory_staking::ID)
+}
+
+pub fn vesting(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VESTING_SEED.as_bytes(), owner.as_ref()],
+        &armory_staking::ID,
+    )
+}
+
+pub fn pool(pool_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POOL_SEED.as_bytes(), &pool_id.to_le_bytes()],
+        &armory_staking::ID,
+    )
+}
+
+pub fn set_bonus_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SET_BONUS_SEED.as_bytes()], &armory_staking::ID)
+}
+
+/// Leaderboard of a pool, 0 for the main pool
+pub fn leaderboard(pool_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LEADERBOARD_SEED.as_bytes(), &pool_id.to_le_bytes()],
+        &armory_staking::ID,
+    )
+}
+
+/// Stake receipt behind a receipt NFT
+pub fn receipt(receipt_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RECEIPT_SEED.as_bytes(), receipt_mint.as_ref()],
+        &armory_staking::ID,
+    )
+}
+
+/// Stake record of a staked bear, readable by other programs
+pub fn stake_record(nft_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[STAKE_RECORD_SEED.as_bytes(), nft_mint.as_ref()],
+        &armory_staking::ID,
+    )
+}
+
+/// Rental listing of a bear by its owner
+pub fn rental_listing(nft_mint: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RENTAL_SEED.as_bytes(), nft_mint.as_ref(), owner.as_ref()],
+        &armory_staking::ID,
+    )
+}
+
+/// Box upgrades of a bear
+pub fn bear_upgrade(bear_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[UPGRADE_SEED.as_bytes(), bear_mint.as_ref()],
+        &armory_staking::ID,
+    )
+}
+
+pub fn governance_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GOVERNANCE_SEED.as_bytes()], &armory_staking::ID)
+}
+
+/// SPL Governance voter weight record of `owner` in `realm`
+pub fn voter_weight_record(
+    realm: &Pubkey,
+    governing_token_mint: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            VOTER_WEIGHT_SEED.as_bytes(),
+            realm.as_ref(),
+            governing_token_mint.as_ref(),
+            owner.as_ref(),
+        ],
+        &armory_staking::ID,
+    )
+}
+
+/// Seed of a user account: `user-pool` for the main pool, `user-pool-<id>` for seasonal pools
+pub fn user_pool_seed(pool_id: u64) -> String {
+    if pool_id == 0 {
+        USER_POOL_SEED.to_string()
+    } else {
+        format!("{}-{}", USER_POOL_SEED, pool_id)
+    }
+}
+
+pub fn user_pool(owner: &Pubkey, pool_id: u64) -> Pubkey {
+    Pubkey::create_with_seed(owner, &user_pool_seed(pool_id), &armory_staking::ID).unwrap()
+}
+
+pub fn metadata(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            mint.as_ref(),
+        ],
+        &mpl_token_metadata::id(),
+    )
+    .0
+}
+
+pub fn edition(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            mint.as_ref(),
+            mpl_token_metadata::state::EDITION.as_bytes(),
+        ],
+        &mpl_token_metadata::id(),
+    )
+    .0
+}
+
+/// Bubblegum tree config of a merkle tree
+pub fn tree_authority(merkle_tree: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[merkle_tree.as_ref()], &mpl_bubblegum::ID).0
+}
+
+/// Asset id of a compressed NFT, what the program stakes it under
+pub fn asset_id(merkle_tree: &Pubkey, nonce: u64) -> Pubkey {
+    mpl_bubblegum::utils::get_asset_id(merkle_tree, nonce)
+}
+
+pub fn token_account(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, mint)
+}
+
+pub fn medal_mint() -> Pubkey {
+    MEDAL_TOKEN_ADDRESS.parse().unwrap()
+}
+
+/// MEDAL vault held by the global authority
+pub fn reward_vault() -> Pubkey {
+    token_account(&global_authority().0, &medal_mint())
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use armory_staking::{account::*, error::StakingError, reward::staked_reward_rate};
+
+/// MEDAL owed to a main pool staker at `now`, including rewards parked by unstaking
+pub fn pending_reward(user_pool: &UserPool, global: &GlobalPool, now: i64) -> Result<u64> {
+    user_pool
+        .total_accrued_reward(now, &global.emission)?
+        .checked_add(user_pool.pending_reward)
+        .ok_or_else(|| StakingError::MathOverflow.into())
+}
+
+/// What `claim_reward` would pay at `now`, where accruals are only taken once a day
+pub fn claimable_reward(user_pool: &UserPool, global: &GlobalPool, now: i64) -> Result<u64> {
+    let mut user_pool = *user_pool;
+    user_pool.claim_reward(now, &global.emission)
+}
+
+/// Co-sponsored token owed at `now` for the reward token in `slot`
+pub fn pending_extra_reward(
+    user_pool: &UserPool,
+    global: &GlobalPool,
+    slot: usize,
+    now: i64,
+) -> Result<u64> {
+    let config = global
+        .reward_tokens
+        .get(slot)
+        .filter(|t| t.mint != Pubkey::default())
+        .ok_or(StakingError::InvalidRewardToken)?;
+    let mut user_pool = *user_pool;
+    user_pool.claim_extra_reward(slot, config, now)
+}
+
+/// Reward owed by a seasonal pool at `now`, which stops accruing at the end of the season
+pub fn pending_pool_reward(user_pool: &UserPool, pool: &Pool, now: i64) -> Result<u64> {
+    user_pool
+        .total_accrued_reward(now.min(pool.end_time), &pool.emission)?
+        .checked_add(user_pool.pending_reward)
+        .ok_or_else(|| StakingError::MathOverflow.into())
+}
+
+/// MEDAL a renter can claim at `now`, before the haircut of an active vesting config
+pub fn pending_rental_reward(
+    listing: &RentalListing,
+    global: &GlobalPool,
+    now: i64,
+) -> Result<u64> {
+    listing.accrued_reward(now, &global.emission)
+}
+
+/// Leaderboard points of a staker at `now`, counting what the next claim or unstake settles
+pub fn pending_points(user_pool: &UserPool, now: i64) -> Result<u64> {
+    let mut user_pool = *user_pool;
+    user_pool.settle_points(now)?;
+    Ok(user_pool.points)
+}
+
+/// Undecayed daily reward of everything staked in `user_pool`
+pub fn daily_reward(user_pool: &UserPool) -> Result<u64> {
+    let mut total: u64 = 0;
+    for staked in user_pool.staked_nfts[..user_pool.staked_count as usize].iter() {
+        total = total
+            .checked_add(staked_reward_rate(staked)?)
+            .ok_or(StakingError::MathOverflow)?;
+    }
+    Ok(total)
+}
@@ -0,0 +1,73 @@
+use anchor_lang::{error::ErrorCode, prelude::*, AccountDeserialize, Discriminator};
+use armory_staking::{account::*, constant::*};
+
+/// Size of a user pool account in the current layout
+pub const USER_POOL_SPACE: usize = 8 + std::mem::size_of::<UserPool>();
+
+pub fn decode_global_pool(data: &[u8]) -> Result<GlobalPool> {
+    GlobalPool::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_pool(data: &[u8]) -> Result<Pool> {
+    Pool::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_vesting(data: &[u8]) -> Result<VestingSchedule> {
+    VestingSchedule::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_set_bonus_config(data: &[u8]) -> Result<SetBonusConfig> {
+    SetBonusConfig::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_leaderboard(data: &[u8]) -> Result<Leaderboard> {
+    Leaderboard::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_stake_receipt(data: &[u8]) -> Result<StakeReceipt> {
+    StakeReceipt::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_stake_record(data: &[u8]) -> Result<StakeRecord> {
+    StakeRecord::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_rental_listing(data: &[u8]) -> Result<RentalListing> {
+    RentalListing::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_bear_upgrade(data: &[u8]) -> Result<BearUpgrade> {
+    BearUpgrade::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_governance_config(data: &[u8]) -> Result<GovernanceConfig> {
+    GovernanceConfig::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_voter_weight_record(data: &[u8]) -> Result<VoterWeightRecord> {
+    VoterWeightRecord::try_deserialize(&mut &data[..])
+}
+
+/// Decode the return data of `view_streak`
+pub fn decode_streak_view(data: &[u8]) -> Result<StreakView> {
+    StreakView::try_from_slice(data).map_err(Into::into)
+}
+
+/// Decode a user pool, upgrading accounts still in the v1 layout the way `migrate_user_pool` would
+pub fn decode_user_pool(data: &[u8]) -> Result<UserPool> {
+    require!(
+        data.len() >= 8 && data[..8] == UserPool::discriminator(),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+
+    let mut user_pool: UserPool = bytemuck::Zeroable::zeroed();
+    if data.len() == USER_POOL_V1_SIZE {
+        user_pool.migrate_from(&UserPoolV1::unpack(data)?);
+        return Ok(user_pool);
+    }
+
+    let size = std::mem::size_of::<UserPool>();
+    require!(data.len() >= 8 + size, ErrorCode::AccountDidNotDeserialize);
+    bytemuck::bytes_of_mut(&mut user_pool).copy_from_slice(&data[8..8 + size]);
+    Ok(user_pool)
+}
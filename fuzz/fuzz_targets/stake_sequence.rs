@@ -0,0 +1,610 @@
+//! Random sequences of `stake_nft`, `unstake_nft`, `claim_reward` and clock advances
+//! against the program booted in `solana-program-test`.
+//!
+//! Run from this directory with `cargo hfuzz run stake_sequence`. After every step the
+//! target checks that
+//! - `GlobalPool.total_staked_count` equals the sum of `staked_count` over user pools,
+//! - escrowed bear and box balances match the `StakedData` entries,
+//! - the vault never paid out more than was accrued plus mission bonuses.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use arbitrary::Arbitrary;
+use armory_staking::{account::*, constant::*, reward::*};
+use honggfuzz::fuzz;
+use mpl_token_metadata::state::{Creator, Data, Key, Metadata, MAX_METADATA_LEN, PREFIX};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::Transaction,
+};
+
+const USERS: usize = 2;
+const BEARS: [&str; 3] = ["Bear #100", "Bear #7999", "Bear #9999"];
+const BOX_ID: u64 = 9600;
+const VAULT_FUNDING: u64 = 1_000_000_000_000;
+const MAX_OPS: usize = 32;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Stake {
+        user: u8,
+        bear: u8,
+        with_box: bool,
+    },
+    Unstake {
+        user: u8,
+        bear: u8,
+        auto_claim: bool,
+    },
+    Claim {
+        user: u8,
+        allow_partial: bool,
+    },
+    Warp {
+        seconds: u32,
+    },
+}
+
+struct Nft {
+    mint: Pubkey,
+    metadata: Pubkey,
+    user_account: Pubkey,
+    escrow_account: Pubkey,
+}
+
+struct User {
+    keypair: Keypair,
+    user_pool: Pubkey,
+    vesting: Pubkey,
+    reward_account: Pubkey,
+    bears: Vec<Nft>,
+    bear_box: Nft,
+}
+
+struct Env {
+    context: ProgramTestContext,
+    global_authority: Pubkey,
+    global_bump: u8,
+    sol_vault: Pubkey,
+    set_bonus_config: Pubkey,
+    leaderboard: Pubkey,
+    reward_vault: Pubkey,
+    users: Vec<User>,
+    /// Upper bound of what unstaked NFTs accrued while they were staked
+    settled_accrual: u128,
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn mint_account(authority: &Pubkey, decimals: u8) -> Account {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(*authority),
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn add_nft(
+    program_test: &mut ProgramTest,
+    owner: &Pubkey,
+    global_authority: &Pubkey,
+    collection: &str,
+    name: &str,
+) -> Nft {
+    let mint = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(owner, 0));
+
+    let (metadata, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            mint.as_ref(),
+        ],
+        &mpl_token_metadata::id(),
+    );
+    let mut data = vec![0; MAX_METADATA_LEN];
+    Metadata {
+        key: Key::MetadataV1,
+        update_authority: *owner,
+        mint,
+        data: Data {
+            name: format!("{:\0<32}", name),
+            symbol: String::new(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: Some(vec![Creator {
+                address: collection.parse().unwrap(),
+                verified: true,
+                share: 100,
+            }]),
+        },
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: None,
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    }
+    .save(&mut data)
+    .unwrap();
+    program_test.add_account(
+        metadata,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: mpl_token_metadata::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let user_account = Pubkey::new_unique();
+    program_test.add_account(user_account, token_account(&mint, owner, 1));
+    let escrow_account = Pubkey::new_unique();
+    program_test.add_account(escrow_account, token_account(&mint, global_authority, 0));
+
+    Nft {
+        mint,
+        metadata,
+        user_account,
+        escrow_account,
+    }
+}
+
+async fn setup() -> Env {
+    let mut program_test = ProgramTest::new(
+        "armory_staking",
+        armory_staking::ID,
+        processor!(armory_staking::entry),
+    );
+    program_test.set_compute_max_units(1_400_000);
+
+    let (global_authority, global_bump) =
+        Pubkey::find_program_address(&[GLOBAL_AUTHORITY_SEED.as_bytes()], &armory_staking::ID);
+    let (sol_vault, _) =
+        Pubkey::find_program_address(&[SOL_VAULT_SEED.as_bytes()], &armory_staking::ID);
+    let (set_bonus_config, _) =
+        Pubkey::find_program_address(&[SET_BONUS_SEED.as_bytes()], &armory_staking::ID);
+    let (leaderboard, _) = Pubkey::find_program_address(
+        &[LEADERBOARD_SEED.as_bytes(), &0u64.to_le_bytes()],
+        &armory_staking::ID,
+    );
+
+    let medal_mint: Pubkey = MEDAL_TOKEN_ADDRESS.parse().unwrap();
+    program_test.add_account(medal_mint, mint_account(&global_authority, 9));
+    let reward_vault = Pubkey::new_unique();
+    program_test.add_account(
+        reward_vault,
+        token_account(&medal_mint, &global_authority, VAULT_FUNDING),
+    );
+
+    let mut users = vec![];
+    for _ in 0..USERS {
+        let keypair = Keypair::new();
+        let owner = keypair.pubkey();
+        program_test.add_account(
+            owner,
+            Account {
+                lamports: 10_000_000_000,
+                data: vec![],
+                owner: system_program::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let reward_account = Pubkey::new_unique();
+        program_test.add_account(reward_account, token_account(&medal_mint, &owner, 0));
+
+        let bears = BEARS
+            .iter()
+            .map(|name| {
+                add_nft(
+                    &mut program_test,
+                    &owner,
+                    &global_authority,
+                    BEAR_COLLECTION_ADDRESS,
+                    name,
+                )
+            })
+            .collect();
+        let bear_box = add_nft(
+            &mut program_test,
+            &owner,
+            &global_authority,
+            BOX_COLLECTION_ADDRESS,
+            "Box #9600",
+        );
+
+        users.push(User {
+            user_pool: Pubkey::create_with_seed(&owner, "user-pool", &armory_staking::ID).unwrap(),
+            vesting: Pubkey::find_program_address(
+                &[VESTING_SEED.as_bytes(), owner.as_ref()],
+                &armory_staking::ID,
+            )
+            .0,
+            keypair,
+            reward_account,
+            bears,
+            bear_box,
+        });
+    }
+
+    let mut env = Env {
+        context: program_test.start_with_context().await,
+        global_authority,
+        global_bump,
+        sol_vault,
+        set_bonus_config,
+        leaderboard,
+        reward_vault,
+        users,
+        settled_accrual: 0,
+    };
+
+    let admin = env.context.payer.pubkey();
+    let initialize = Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::Initialize {
+            admin,
+            global_authority,
+            sol_vault: env.sol_vault,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::Initialize {
+            _global_bump: global_bump,
+        }
+        .data(),
+    };
+    process(&mut env, &[initialize], None).await.unwrap();
+
+    let space = 8 + std::mem::size_of::<UserPool>();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    for i in 0..USERS {
+        let owner = env.users[i].keypair.pubkey();
+        let user_pool = env.users[i].user_pool;
+        let create_user_pool = system_instruction::create_account_with_seed(
+            &owner,
+            &user_pool,
+            &owner,
+            "user-pool",
+            rent.minimum_balance(space),
+            space as u64,
+            &armory_staking::ID,
+        );
+        let init_user_pool = Instruction {
+            program_id: armory_staking::ID,
+            accounts: armory_staking::accounts::InitUserPool { user_pool, owner }
+                .to_account_metas(None),
+            data: armory_staking::instruction::InitUserPool {}.data(),
+        };
+        process(&mut env, &[create_user_pool, init_user_pool], Some(i))
+            .await
+            .unwrap();
+    }
+
+    env
+}
+
+async fn process(
+    env: &mut Env,
+    instructions: &[Instruction],
+    user: Option<usize>,
+) -> Result<(), BanksClientError> {
+    let blockhash = env
+        .context
+        .banks_client
+        .get_new_latest_blockhash(&env.context.last_blockhash)
+        .await
+        .unwrap();
+    env.context.last_blockhash = blockhash;
+
+    let mut signers = vec![&env.context.payer];
+    signers.extend(user.map(|i| &env.users[i].keypair));
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&env.context.payer.pubkey()),
+        &signers,
+        blockhash,
+    );
+    env.context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+}
+
+async fn now(env: &mut Env) -> i64 {
+    let clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp
+}
+
+async fn token_amount(env: &mut Env, address: Pubkey) -> u64 {
+    let account = env.context.banks_client.get_account(address).await.unwrap();
+    spl_token::state::Account::unpack(&account.unwrap().data)
+        .unwrap()
+        .amount
+}
+
+async fn global_pool(env: &mut Env) -> GlobalPool {
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.global_authority)
+        .await
+        .unwrap();
+    GlobalPool::try_deserialize(&mut account.unwrap().data.as_slice()).unwrap()
+}
+
+async fn user_pool(env: &mut Env, user: usize) -> UserPool {
+    let address = env.users[user].user_pool;
+    let account = env.context.banks_client.get_account(address).await.unwrap();
+    bytemuck::pod_read_unaligned(&account.unwrap().data[8..8 + std::mem::size_of::<UserPool>()])
+}
+
+/// Flat accrual with the streak bonus from `staked_time` to `now`, an upper bound for any
+/// emission schedule
+fn accrual_bound(staked: &StakedData, now: i64) -> u128 {
+    let rate = staked_reward_rate(staked).unwrap();
+    streak_accrued(staked.staked_time, staked.staked_time, now, |from, to| {
+        linear_accrued(rate, from, to)
+    })
+    .unwrap() as u128
+}
+
+fn stake_ix(env: &Env, user: usize, bear: usize, with_box: bool) -> Instruction {
+    let user = &env.users[user];
+    let bear = &user.bears[bear];
+    Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::StakeNft {
+            owner: user.keypair.pubkey(),
+            global_authority: env.global_authority,
+            user_pool: user.user_pool,
+            nft_mint: bear.mint,
+            nft_box_mint: user.bear_box.mint,
+            user_bear_account: bear.user_account,
+            dest_bear_account: bear.escrow_account,
+            user_box_account: user.bear_box.user_account,
+            dest_box_account: user.bear_box.escrow_account,
+            reward_vault: env.reward_vault,
+            user_reward_account: user.reward_account,
+            mint_metadata: bear.metadata,
+            bear_upgrade: BearUpgrade::address(&bear.mint),
+            stake_record: StakeRecord::address(&bear.mint),
+            set_bonus_config: env.set_bonus_config,
+            sol_vault: env.sol_vault,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+            token_metadata_program: mpl_token_metadata::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::StakeNft {
+            _global_bump: env.global_bump,
+            _box_id: if with_box { BOX_ID } else { 0 },
+            rarity_rate: 0,
+            rarity_proof: vec![],
+        }
+        .data(),
+    }
+}
+
+fn unstake_ix(env: &Env, user: usize, bear: usize, box_id: u64, auto_claim: bool) -> Instruction {
+    let user = &env.users[user];
+    let bear = &user.bears[bear];
+    let mut instruction = Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::UnstakeNft {
+            owner: user.keypair.pubkey(),
+            user_pool: user.user_pool,
+            global_authority: env.global_authority,
+            nft_mint: bear.mint,
+            nft_box_mint: user.bear_box.mint,
+            user_bear_account: bear.user_account,
+            dest_bear_account: bear.escrow_account,
+            user_box_account: user.bear_box.user_account,
+            dest_box_account: user.bear_box.escrow_account,
+            leaderboard: env.leaderboard,
+            stake_record: StakeRecord::address(&bear.mint),
+            set_bonus_config: env.set_bonus_config,
+            sol_vault: env.sol_vault,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::UnstakeNft {
+            _global_bump: env.global_bump,
+            _box_id: box_id,
+        }
+        .data(),
+    };
+    if auto_claim {
+        instruction.accounts.extend([
+            AccountMeta::new(env.reward_vault, false),
+            AccountMeta::new(user.reward_account, false),
+        ]);
+    }
+    instruction
+}
+
+fn claim_ix(env: &Env, user: usize, allow_partial: bool) -> Instruction {
+    let user = &env.users[user];
+    Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::ClaimReward {
+            claimer: user.keypair.pubkey(),
+            user_pool: user.user_pool,
+            global_authority: env.global_authority,
+            reward_vault: env.reward_vault,
+            user_reward_account: user.reward_account,
+            vesting: user.vesting,
+            leaderboard: env.leaderboard,
+            sol_vault: env.sol_vault,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::ClaimReward {
+            _global_bump: env.global_bump,
+            allow_partial,
+            instant: false,
+            reward_token_slots: vec![],
+        }
+        .data(),
+    }
+}
+
+async fn run(env: &mut Env, op: Op) {
+    match op {
+        Op::Stake {
+            user,
+            bear,
+            with_box,
+        } => {
+            let (user, bear) = (user as usize % USERS, bear as usize % BEARS.len());
+            let ix = stake_ix(env, user, bear, with_box);
+            let _ = process(env, &[ix], Some(user)).await;
+        }
+        Op::Unstake {
+            user,
+            bear,
+            auto_claim,
+        } => {
+            let (user, bear) = (user as usize % USERS, bear as usize % BEARS.len());
+            let pool = user_pool(env, user).await;
+            let staked = pool
+                .find_nft(&env.users[user].bears[bear].mint)
+                .map(|index| pool.staked_nfts[index]);
+            let box_id = staked.map_or(0, |staked| staked.box_id);
+
+            let ix = unstake_ix(env, user, bear, box_id, auto_claim);
+            if process(env, &[ix], Some(user)).await.is_ok() {
+                let staked = staked.expect("unstaked an NFT that was not staked");
+                let now = now(env).await;
+                env.settled_accrual += accrual_bound(&staked, now);
+            }
+        }
+        Op::Claim {
+            user,
+            allow_partial,
+        } => {
+            let user = user as usize % USERS;
+            let ix = claim_ix(env, user, allow_partial);
+            let _ = process(env, &[ix], Some(user)).await;
+        }
+        Op::Warp { seconds } => {
+            let mut clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+            clock.unix_timestamp += seconds as i64;
+            env.context.set_sysvar(&clock);
+        }
+    }
+}
+
+async fn check_invariants(env: &mut Env) {
+    let now = now(env).await;
+    let mut staked_count: u64 = 0;
+    let mut accrual = env.settled_accrual;
+    let mut bonuses: u128 = 0;
+
+    for user in 0..USERS {
+        let pool = user_pool(env, user).await;
+        staked_count += pool.staked_count;
+        if pool.mission_completed {
+            bonuses += MISSION_BONUS as u128;
+        }
+        let staked = &pool.staked_nfts[..pool.staked_count as usize];
+        for staked in staked {
+            accrual += accrual_bound(staked, now);
+        }
+
+        for bear in 0..BEARS.len() {
+            let (mint, escrow, wallet) = {
+                let nft = &env.users[user].bears[bear];
+                (nft.mint, nft.escrow_account, nft.user_account)
+            };
+            let is_staked = staked.iter().any(|staked| staked.bear_mint == mint) as u64;
+            assert_eq!(token_amount(env, escrow).await, is_staked);
+            assert_eq!(token_amount(env, wallet).await, 1 - is_staked);
+        }
+
+        let (box_mint, box_escrow) = {
+            let nft = &env.users[user].bear_box;
+            (nft.mint, nft.escrow_account)
+        };
+        let boxed = staked
+            .iter()
+            .filter(|staked| staked.box_id != 0 && staked.box_mint == box_mint)
+            .count() as u64;
+        assert_eq!(token_amount(env, box_escrow).await, boxed);
+    }
+
+    assert_eq!(global_pool(env).await.total_staked_count, staked_count);
+
+    let paid = VAULT_FUNDING - token_amount(env, env.reward_vault).await;
+    assert!(
+        paid as u128 <= accrual + bonuses,
+        "vault paid {} but only {} accrued",
+        paid,
+        accrual + bonuses
+    );
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    loop {
+        fuzz!(|ops: Vec<Op>| {
+            runtime.block_on(async {
+                let mut env = setup().await;
+                for op in ops.into_iter().take(MAX_OPS) {
+                    run(&mut env, op).await;
+                    check_invariants(&mut env).await;
+                }
+            });
+        });
+    }
+}
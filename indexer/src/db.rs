@@ -0,0 +1,270 @@
+//! SQLite schema and writers. Account snapshots overwrite the current state, events are
+//! keyed by (signature, index) so replaying the same dump is harmless.
+
+use anchor_lang::prelude::Pubkey;
+use armory_staking::{account::*, constant::MISSION_BONUS};
+use rusqlite::{params, Connection};
+
+use crate::decode::{Event, Transaction};
+use crate::IndexResult;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS global_pool (
+    address TEXT PRIMARY KEY,
+    super_admin TEXT NOT NULL,
+    total_staked_count INTEGER NOT NULL,
+    vesting_duration INTEGER NOT NULL,
+    instant_haircut_bps INTEGER NOT NULL,
+    stake_fee INTEGER NOT NULL,
+    unstake_fee INTEGER NOT NULL,
+    claim_fee INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS stakers (
+    user_pool TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    pool_id INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    staked_count INTEGER NOT NULL,
+    pending_reward INTEGER NOT NULL,
+    last_claimed_time INTEGER NOT NULL,
+    mission_completed INTEGER NOT NULL,
+    claim_delegate TEXT,
+    reward_destination TEXT NOT NULL,
+    points INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS stakers_owner ON stakers (owner);
+CREATE TABLE IF NOT EXISTS staked_nfts (
+    bear_mint TEXT PRIMARY KEY,
+    user_pool TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    pool_id INTEGER NOT NULL,
+    bear_id INTEGER NOT NULL,
+    box_mint TEXT,
+    box_id INTEGER NOT NULL,
+    staked_time INTEGER NOT NULL,
+    reward_rate INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS staked_nfts_owner ON staked_nfts (owner);
+CREATE TABLE IF NOT EXISTS leaderboard (
+    pool_id INTEGER NOT NULL,
+    rank INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    points INTEGER NOT NULL,
+    PRIMARY KEY (pool_id, rank)
+);
+CREATE TABLE IF NOT EXISTS stake_receipts (
+    receipt TEXT PRIMARY KEY,
+    bear_mint TEXT NOT NULL,
+    bear_id INTEGER NOT NULL,
+    box_mint TEXT,
+    box_id INTEGER NOT NULL,
+    staked_time INTEGER NOT NULL,
+    reward_rate INTEGER NOT NULL,
+    last_claimed_time INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS claims (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    vested INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS claims_owner ON claims (owner);
+CREATE TABLE IF NOT EXISTS shortfalls (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    claimed INTEGER NOT NULL,
+    shortfall INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE TABLE IF NOT EXISTS missions (
+    owner TEXT PRIMARY KEY,
+    bonus INTEGER NOT NULL,
+    timestamp INTEGER,
+    signature TEXT
+);
+";
+
+pub fn open(path: &str) -> IndexResult<Connection> {
+    let conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+pub fn write_global_pool(conn: &Connection, address: &Pubkey, global: &GlobalPool) -> IndexResult {
+    conn.execute(
+        "INSERT OR REPLACE INTO global_pool VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            address.to_string(),
+            global.super_admin.to_string(),
+            global.total_staked_count,
+            global.vesting_duration,
+            global.instant_haircut_bps,
+            global.stake_fee,
+            global.unstake_fee,
+            global.claim_fee,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Replace a staker row and its staked NFTs with the snapshot
+pub fn write_user_pool(
+    conn: &mut Connection,
+    address: &Pubkey,
+    user_pool: &UserPool,
+) -> IndexResult {
+    let address = address.to_string();
+    let owner = user_pool.owner.to_string();
+    let claim_delegate = (user_pool.claim_delegate != Pubkey::default())
+        .then(|| user_pool.claim_delegate.to_string());
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT OR REPLACE INTO stakers VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            address,
+            owner,
+            user_pool.pool_id,
+            user_pool.version,
+            user_pool.staked_count,
+            user_pool.pending_reward,
+            user_pool.last_claimed_time,
+            user_pool.mission_completed,
+            claim_delegate,
+            user_pool.reward_destination().to_string(),
+            user_pool.points,
+        ],
+    )?;
+
+    tx.execute(
+        "DELETE FROM staked_nfts WHERE user_pool = ?1",
+        params![address],
+    )?;
+    for staked in user_pool.staked_nfts[..user_pool.staked_count as usize].iter() {
+        let box_mint = (staked.box_id != 0).then(|| staked.box_mint.to_string());
+        tx.execute(
+            "INSERT OR REPLACE INTO staked_nfts VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                staked.bear_mint.to_string(),
+                address,
+                owner,
+                user_pool.pool_id,
+                staked.bear_id,
+                box_mint,
+                staked.box_id,
+                staked.staked_time,
+                staked.reward_rate,
+            ],
+        )?;
+    }
+
+    // Missions completed before events were emitted are only known from the flag
+    if user_pool.mission_completed && user_pool.pool_id == 0 {
+        tx.execute(
+            "INSERT OR IGNORE INTO missions (owner, bonus) VALUES (?1, ?2)",
+            params![owner, MISSION_BONUS],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Replace the ranking of a pool with the snapshot, ranks starting at 1
+pub fn write_leaderboard(conn: &mut Connection, leaderboard: &Leaderboard) -> IndexResult {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM leaderboard WHERE pool_id = ?1",
+        params![leaderboard.pool_id],
+    )?;
+    for (rank, entry) in leaderboard.entries.iter().enumerate() {
+        if entry.owner != Pubkey::default() {
+            tx.execute(
+                "INSERT INTO leaderboard VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    leaderboard.pool_id,
+                    rank + 1,
+                    entry.owner.to_string(),
+                    entry.points
+                ],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn write_stake_receipt(
+    conn: &Connection,
+    address: &Pubkey,
+    receipt: &StakeReceipt,
+) -> IndexResult {
+    let box_mint = (receipt.box_id != 0).then(|| receipt.box_mint.to_string());
+    conn.execute(
+        "INSERT OR REPLACE INTO stake_receipts VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            address.to_string(),
+            receipt.bear_mint.to_string(),
+            receipt.bear_id,
+            box_mint,
+            receipt.box_id,
+            receipt.staked_time,
+            receipt.reward_rate,
+            receipt.last_claimed_time,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn write_transaction(conn: &mut Connection, transaction: &Transaction) -> IndexResult {
+    let tx = conn.transaction()?;
+    for (index, event) in transaction.events.iter().enumerate() {
+        match event {
+            Event::RewardClaimed(event) => tx.execute(
+                "INSERT OR REPLACE INTO claims VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    transaction.signature,
+                    index,
+                    transaction.slot,
+                    event.owner.to_string(),
+                    event.mint.to_string(),
+                    event.amount,
+                    event.vested,
+                    event.timestamp,
+                ],
+            )?,
+            Event::RewardShortfall(event) => tx.execute(
+                "INSERT OR REPLACE INTO shortfalls VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    transaction.signature,
+                    index,
+                    transaction.slot,
+                    event.owner.to_string(),
+                    event.mint.to_string(),
+                    event.claimed,
+                    event.shortfall,
+                    event.timestamp,
+                ],
+            )?,
+            Event::MissionCompleted(event) => tx.execute(
+                "INSERT OR REPLACE INTO missions VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    event.owner.to_string(),
+                    event.bonus,
+                    event.timestamp,
+                    transaction.signature,
+                ],
+            )?,
+        };
+    }
+    tx.commit()?;
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Decoding of program accounts and Anchor events out of RPC style JSON.
+
+use anchor_lang::{prelude::*, Discriminator};
+use armory_staking::{account::*, event::*};
+use armory_staking_client::state;
+use serde_json::Value;
+
+use crate::IndexResult;
+
+pub enum ProgramAccount {
+    Global(Box<GlobalPool>),
+    User(Box<UserPool>),
+    Leaderboard(Box<Leaderboard>),
+    Receipt(Box<StakeReceipt>),
+}
+
+pub enum Event {
+    RewardClaimed(RewardClaimed),
+    RewardShortfall(RewardShortfall),
+    MissionCompleted(MissionCompleted),
+}
+
+/// A transaction reduced to what the indexer stores
+pub struct Transaction {
+    pub signature: String,
+    pub slot: u64,
+    pub events: Vec<Event>,
+}
+
+/// Accept a single object or an array of them
+fn entries(json: Value) -> Vec<Value> {
+    match json {
+        Value::Array(entries) => entries,
+        entry => vec![entry],
+    }
+}
+
+/// Decode an account by its discriminator, skipping accounts the indexer does not track
+pub fn decode_account(data: &[u8]) -> IndexResult<Option<ProgramAccount>> {
+    if data.len() < 8 {
+        return Ok(None);
+    }
+    let discriminator = &data[..8];
+    let account = if discriminator == GlobalPool::discriminator() {
+        ProgramAccount::Global(Box::new(
+            state::decode_global_pool(data).map_err(|e| e.to_string())?,
+        ))
+    } else if discriminator == UserPool::discriminator() {
+        ProgramAccount::User(Box::new(
+            state::decode_user_pool(data).map_err(|e| e.to_string())?,
+        ))
+    } else if discriminator == Leaderboard::discriminator() {
+        ProgramAccount::Leaderboard(Box::new(
+            state::decode_leaderboard(data).map_err(|e| e.to_string())?,
+        ))
+    } else if discriminator == StakeReceipt::discriminator() {
+        ProgramAccount::Receipt(Box::new(
+            state::decode_stake_receipt(data).map_err(|e| e.to_string())?,
+        ))
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(account))
+}
+
+/// Parse `getProgramAccounts` or `solana account --output json` output with base64 data
+pub fn parse_accounts(json: Value) -> IndexResult<Vec<(Pubkey, Vec<u8>)>> {
+    let mut accounts = vec![];
+    for entry in entries(json) {
+        let pubkey = entry["pubkey"]
+            .as_str()
+            .ok_or("account entry without pubkey")?
+            .parse::<Pubkey>()?;
+        let data = match &entry["account"]["data"] {
+            Value::Array(data) if data.get(1).and_then(Value::as_str) == Some("base64") => {
+                data[0].as_str().ok_or("account data is not a string")?
+            }
+            _ => return Err(format!("account {} is not base64 encoded", pubkey).into()),
+        };
+        accounts.push((pubkey, base64::decode(data)?));
+    }
+    Ok(accounts)
+}
+
+/// Parse `getTransaction` results, or `{ signature, slot, logs }` entries from `logsSubscribe`
+pub fn parse_transactions(json: Value) -> IndexResult<Vec<Transaction>> {
+    let mut transactions = vec![];
+    for entry in entries(json) {
+        let signature = entry["signature"]
+            .as_str()
+            .or_else(|| entry["transaction"]["signatures"][0].as_str())
+            .ok_or("transaction entry without signature")?
+            .to_string();
+        let logs = entry["logs"]
+            .as_array()
+            .or_else(|| entry["meta"]["logMessages"].as_array())
+            .ok_or_else(|| format!("transaction {} has no logs", signature))?;
+        let logs: Vec<&str> = logs.iter().filter_map(Value::as_str).collect();
+        // Failed transactions still log the events they emitted before reverting
+        let failed = !entry["err"].is_null() || !entry["meta"]["err"].is_null();
+
+        transactions.push(Transaction {
+            signature,
+            slot: entry["slot"].as_u64().unwrap_or_default(),
+            events: if failed {
+                vec![]
+            } else {
+                program_events(&logs)
+            },
+        });
+    }
+    Ok(transactions)
+}
+
+/// Events emitted by this program, ignoring `Program data:` lines of other programs it calls
+pub fn program_events(logs: &[&str]) -> Vec<Event> {
+    let program_invoke = format!("Program {} invoke", armory_staking::ID);
+    let mut stack: Vec<bool> = vec![];
+    let mut events = vec![];
+
+    for log in logs {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if stack.last() == Some(&true) {
+                events.extend(base64::decode(data).ok().and_then(|d| decode_event(&d)));
+            }
+        } else if log.starts_with("Program ") && log.contains(" invoke [") {
+            stack.push(log.starts_with(&program_invoke));
+        } else if log.starts_with("Program ")
+            && (log.ends_with(" success") || log.contains(" failed"))
+        {
+            stack.pop();
+        }
+    }
+    events
+}
+
+fn decode_event(data: &[u8]) -> Option<Event> {
+    if data.len() < 8 {
+        return None;
+    }
+    let (discriminator, mut body) = data.split_at(8);
+    if discriminator == RewardClaimed::discriminator() {
+        RewardClaimed::deserialize(&mut body)
+            .ok()
+            .map(Event::RewardClaimed)
+    } else if discriminator == RewardShortfall::discriminator() {
+        RewardShortfall::deserialize(&mut body)
+            .ok()
+            .map(Event::RewardShortfall)
+    } else if discriminator == MissionCompleted::discriminator() {
+        MissionCompleted::deserialize(&mut body)
+            .ok()
+            .map(Event::MissionCompleted)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,139 @@
+//! Indexes Armory Staking into SQLite for dashboards.
+//!
+//! `accounts` snapshots global pools, user pools, leaderboards and stake receipts into
+//! `global_pool`, `stakers`, `staked_nfts`, `leaderboard` and `stake_receipts`.
+//! `transactions` decodes Anchor events from transaction logs into `claims`, `shortfalls`
+//! and `missions`. Both read a JSON dump (`--file`) or an RPC node (`--url`), e.g. a local
+//! `solana-test-validator`.
+
+mod db;
+mod decode;
+
+use clap::{Parser, Subcommand};
+use serde_json::Value;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_transaction_status::UiTransactionEncoding;
+
+use decode::ProgramAccount;
+
+pub type IndexResult<T = ()> = Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[clap(
+    name = "armory-indexer",
+    version,
+    about = "Index Armory Staking accounts and events into SQLite"
+)]
+struct Cli {
+    /// SQLite database, created on first run
+    #[clap(long, default_value = "armory.sqlite")]
+    db: String,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Snapshot global pools, user pools, leaderboards and stake receipts
+    Accounts {
+        /// `getProgramAccounts` JSON dump with base64 data
+        #[clap(long, conflicts_with = "url")]
+        file: Option<String>,
+        #[clap(long)]
+        url: Option<String>,
+    },
+    /// Decode events from transaction logs
+    Transactions {
+        /// `getTransaction` results or `logsSubscribe` notifications as a JSON array
+        #[clap(long, conflicts_with = "url")]
+        file: Option<String>,
+        #[clap(long)]
+        url: Option<String>,
+        /// Most recent program transactions to fetch over RPC
+        #[clap(long, default_value_t = 1000)]
+        limit: usize,
+    },
+}
+
+fn read_json(path: &str) -> IndexResult<Value> {
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn rpc(url: String) -> RpcClient {
+    RpcClient::new_with_commitment(url, CommitmentConfig::confirmed())
+}
+
+fn main() -> IndexResult {
+    let cli = Cli::parse();
+    let mut conn = db::open(&cli.db)?;
+
+    match cli.command {
+        Command::Accounts { file, url } => {
+            let accounts = match (file, url) {
+                (Some(file), _) => decode::parse_accounts(read_json(&file)?)?,
+                (None, Some(url)) => rpc(url)
+                    .get_program_accounts(&armory_staking::ID)?
+                    .into_iter()
+                    .map(|(pubkey, account)| (pubkey, account.data))
+                    .collect(),
+                (None, None) => return Err("pass --file or --url".into()),
+            };
+
+            let (mut globals, mut users) = (0, 0);
+            for (address, data) in accounts {
+                match decode::decode_account(&data)? {
+                    Some(ProgramAccount::Global(global)) => {
+                        db::write_global_pool(&conn, &address, &global)?;
+                        globals += 1;
+                    }
+                    Some(ProgramAccount::User(user_pool)) => {
+                        db::write_user_pool(&mut conn, &address, &user_pool)?;
+                        users += 1;
+                    }
+                    Some(ProgramAccount::Leaderboard(leaderboard)) => {
+                        db::write_leaderboard(&mut conn, &leaderboard)?;
+                    }
+                    Some(ProgramAccount::Receipt(receipt)) => {
+                        db::write_stake_receipt(&conn, &address, &receipt)?;
+                    }
+                    None => {}
+                }
+            }
+            println!("indexed {} global and {} user pools", globals, users);
+        }
+        Command::Transactions { file, url, limit } => {
+            let json = match (file, url) {
+                (Some(file), _) => read_json(&file)?,
+                (None, Some(url)) => {
+                    let rpc = rpc(url);
+                    let mut transactions = vec![];
+                    let signatures = rpc.get_signatures_for_address(&armory_staking::ID)?;
+                    for status in signatures.into_iter().take(limit) {
+                        let transaction = rpc.get_transaction(
+                            &status.signature.parse()?,
+                            UiTransactionEncoding::Json,
+                        )?;
+                        transactions.push(serde_json::to_value(&transaction)?);
+                    }
+                    Value::Array(transactions)
+                }
+                (None, None) => return Err("pass --file or --url".into()),
+            };
+
+            let transactions = decode::parse_transactions(json)?;
+            let mut events = 0;
+            for transaction in transactions.iter() {
+                db::write_transaction(&mut conn, transaction)?;
+                events += transaction.events.len();
+            }
+            println!(
+                "indexed {} events from {} transactions",
+                events,
+                transactions.len()
+            );
+        }
+    }
+    Ok(())
+}
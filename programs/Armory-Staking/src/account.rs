@@ -3,34 +3,493 @@ use std::clone::Clone;
 
 use crate::constant::*;
 use crate::error::*;
+use crate::reward::*;
 
 #[account]
 #[derive(Default)]
 pub struct GlobalPool {
-    //Total Size: 8 + 40 = 48
-    pub super_admin: Pubkey,     //32
-    pub total_staked_count: u64, //8
+    //Total Size: 8 + 584 = 592
+    pub super_admin: Pubkey,                                   //32
+    pub total_staked_count: u64,                               //8
+    pub emission: EmissionSchedule,                            //24
+    pub rarity_root: [u8; 32],                                 //32
+    pub vesting_duration: i64,                                 //8, 0 pays claims out immediately
+    pub instant_haircut_bps: u64,                              //8
+    pub stake_fee: u64,                                        //8, lamports
+    pub unstake_fee: u64,                                      //8, lamports
+    pub claim_fee: u64,                                        //8, lamports
+    pub reward_tokens: [RewardTokenConfig; MAX_REWARD_TOKENS], //112 * 4
+}
+
+impl GlobalPool {
+    pub const SIZE: usize = 592;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RewardTokenConfig {
+    pub mint: Pubkey,                                   //32, default for an unused slot
+    pub rate_per_medal: u64, //8, base units per day for each MEDAL of bear rate
+    pub start_time: i64,     //8
+    pub previous_rates: [RateChange; MAX_RATE_CHANGES], //16 * 4, oldest first
+}
+
+/// Rate a reward token paid before an admin changed it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RateChange {
+    pub rate_per_medal: u64, //8
+    pub until: i64,          //8, 0 for an unused entry
+}
+
+/// Returned by `view_streak`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreakView {
+    pub staked_time: i64,
+    pub bonus_bps: u64,
+    pub next_bonus_bps: u64,  // same as bonus_bps at the top tier
+    pub next_bonus_time: i64, // 0 at the top tier
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EmissionSchedule {
+    pub start_time: i64,   //8
+    pub epoch_length: i64, //8, 0 disables decay
+    pub decay_bps: u64,    //8, multiplier kept per epoch
+}
+
+/// Seasonal or partner pool. Pool 0 is the main pool run from `GlobalPool`.
+#[account]
+#[derive(Default)]
+pub struct Pool {
+    //Total Size: 8 + 216 = 224
+    pub pool_id: u64,               //8
+    pub admin: Pubkey,              //32
+    pub bear_collection: Pubkey,    //32
+    pub box_collection: Pubkey,     //32
+    pub reward_mint: Pubkey,        //32
+    pub rarity_root: [u8; 32],      //32
+    pub emission: EmissionSchedule, //24
+    pub start_time: i64,            //8
+    pub end_time: i64,              //8
+    pub staked_count: u64,          //8
+}
+
+/// Locked part of one claim, released linearly until `end_time`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct VestingTranche {
+    pub locked_amount: u64, //8
+    pub start_time: i64,    //8, last settlement
+    pub end_time: i64,      //8
+}
+
+impl VestingTranche {
+    /// Release the part vested since the last settlement
+    fn settle(&mut self, now: i64) -> u64 {
+        if self.locked_amount == 0 || now <= self.start_time {
+            return 0;
+        }
+
+        let released = if now >= self.end_time {
+            self.locked_amount
+        } else {
+            (self.locked_amount as u128 * (now - self.start_time) as u128
+                / (self.end_time - self.start_time) as u128) as u64
+        };
+        self.locked_amount -= released;
+        self.start_time = now;
+        released
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct VestingSchedule {
+    //Total Size: 8 + 432 = 440
+    pub owner: Pubkey,                                    //32
+    pub locked_amount: u64,                               //8, sum of the tranches
+    pub claimable_amount: u64,                            //8
+    pub tranches: [VestingTranche; MAX_VESTING_TRANCHES], //24 * 16 = 384
+}
+
+impl VestingSchedule {
+    /// Move the part of each tranche released since its last settlement into the claimable amount
+    pub fn settle(&mut self, now: i64) {
+        for tranche in self.tranches.iter_mut() {
+            let released = tranche.settle(now);
+            self.claimable_amount += released;
+            self.locked_amount -= released;
+        }
+    }
+
+    /// Lock `amount` until `duration` from now, rounded up to a whole day, without touching
+    /// what earlier claims still have locked. Claims ending the same day share a tranche, and a
+    /// full schedule extends its last tranche to the new end instead.
+    pub fn add(&mut self, amount: u64, now: i64, duration: i64) {
+        self.settle(now);
+        let end_time = (now + duration + ONE_DAY - 1) / ONE_DAY * ONE_DAY;
+        let slot = match self
+            .tranches
+            .iter()
+            .position(|tranche| tranche.locked_amount > 0 && tranche.end_time == end_time)
+        {
+            Some(slot) => slot,
+            None => match self
+                .tranches
+                .iter()
+                .position(|tranche| tranche.locked_amount == 0)
+            {
+                Some(slot) => slot,
+                None => {
+                    let (slot, _) = self
+                        .tranches
+                        .iter()
+                        .enumerate()
+                        .max_by_key(|(_, tranche)| tranche.end_time)
+                        .unwrap();
+                    slot
+                }
+            },
+        };
+        let tranche = &mut self.tranches[slot];
+        tranche.locked_amount += amount;
+        tranche.start_time = now;
+        tranche.end_time = end_time;
+        self.locked_amount += amount;
+    }
+}
+
+/// Position of a bear moved out of its user pool, owned by whoever holds the receipt NFT
+#[account]
+#[derive(Default)]
+pub struct StakeReceipt {
+    //Total Size: 8 + 104 = 112
+    pub bear_mint: Pubkey,      //32
+    pub bear_id: u64,           //8
+    pub box_mint: Pubkey,       //32
+    pub box_id: u64,            //8
+    pub staked_time: i64,       //8
+    pub reward_rate: u64,       //8
+    pub last_claimed_time: i64, //8
+}
+
+impl StakeReceipt {
+    pub fn staked(&self) -> StakedData {
+        StakedData {
+            bear_mint: self.bear_mint,
+            bear_id: self.bear_id,
+            box_mint: self.box_mint,
+            box_id: self.box_id,
+            staked_time: self.staked_time,
+            reward_rate: self.reward_rate,
+            ..Default::default()
+        }
+    }
+
+    /// MEDAL owed to the holder at `now`. The streak carries over from the user pool,
+    /// set bonuses stay with the user pool.
+    pub fn accrued_reward(&self, now: i64, schedule: &EmissionSchedule) -> Result<u64> {
+        let reward_rate = staked_reward_rate(&self.staked())?;
+        let last_claimed_time = self.last_claimed_time.max(self.staked_time);
+        streak_accrued(self.staked_time, last_claimed_time, now, |from, to| {
+            schedule.accrued(reward_rate, from, to)
+        })
+    }
+}
+
+/// Proof that a bear is staked, at a PDA seeded by its mint so other programs can look it up.
+/// Created on stake and closed on unstake.
+#[account]
+#[derive(Default)]
+pub struct StakeRecord {
+    //Total Size: 8 + 137 = 145
+    pub owner: Pubkey,    //32, the stake receipt PDA while a receipt holds the bear
+    pub nft_mint: Pubkey, //32
+    pub pool_id: u64,     //8, 0 for the main pool
+    pub staked_time: i64, //8
+    pub tier: u8,         //1, index in BEAR_TIER_RATES, u8::MAX for rates off the table
+    pub box_mint: Pubkey, //32, default when staked without a box
+    pub box_id: u64,      //8
+    pub reward_rate: u64, //8, daily MEDAL of the bear, its voting weight
+    pub vote_lock_until: i64, //8, the bear cannot leave its pool before, once it voted
+}
+
+impl StakeRecord {
+    pub const SIZE: usize = 145;
+
+    pub fn address(nft_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[STAKE_RECORD_SEED.as_bytes(), nft_mint.as_ref()],
+            &crate::ID,
+        )
+        .0
+    }
+
+    pub fn record(&mut self, owner: Pubkey, pool_id: u64, staked: &StakedData) {
+        self.owner = owner;
+        self.nft_mint = staked.bear_mint;
+        self.pool_id = pool_id;
+        self.staked_time = staked.staked_time;
+        self.tier = bear_tier(staked).map_or(u8::MAX, |tier| tier as u8);
+        self.reward_rate = if staked.reward_rate != 0 {
+            staked.reward_rate
+        } else {
+            bear_rate(staked.bear_id).unwrap_or(0)
+        };
+        if staked.box_id != 0 {
+            self.box_mint = staked.box_mint;
+            self.box_id = staked.box_id;
+        }
+    }
+
+    /// Read the record of `nft_mint` from `info`, `None` when the bear is not staked
+    pub fn load(info: &AccountInfo, nft_mint: &Pubkey) -> Option<StakeRecord> {
+        if info.key() != Self::address(nft_mint) || info.owner != &crate::ID {
+            return None;
+        }
+        let data = info.try_borrow_data().ok()?;
+        StakeRecord::try_deserialize(&mut &data[..]).ok()
+    }
+
+    /// Voting power of the bear at `now`
+    pub fn voting_power(&self, now: i64, config: &GovernanceConfig) -> Result<u64> {
+        voting_power(
+            self.reward_rate,
+            self.staked_time,
+            now,
+            config.duration_bonus_bps,
+            config.duration_cap,
+        )
+    }
+
+    pub fn is_vote_locked(&self, now: i64) -> bool {
+        now < self.vote_lock_until
+    }
+}
+
+/// Whether `owner` stakes `nft_mint`, read straight from its stake record without a CPI
+#[cfg(feature = "cpi")]
+pub fn is_staked(stake_record: &AccountInfo, nft_mint: &Pubkey, owner: &Pubkey) -> bool {
+    matches!(StakeRecord::load(stake_record, nft_mint), Some(record) if record.owner == *owner)
+}
+
+/// Offer to rent out the reward stream of a staked bear. One rental runs at a time, the next one
+/// can start once it expired and the renter was paid everything it accrued.
+#[account]
+#[derive(Default)]
+pub struct RentalListing {
+    //Total Size: 8 + 153 = 161
+    pub owner: Pubkey,      //32
+    pub nft_mint: Pubkey,   //32
+    pub price: u64,         //8, lamports or MEDAL base units
+    pub duration: i64,      //8
+    pub pay_in_medal: bool, //1
+    pub renter: Pubkey,     //32
+    pub rent_start: i64,    //8
+    pub rent_end: i64,      //8
+    pub claimed_until: i64, //8
+    pub staked_time: i64,   //8, of the bear when rented, for its streak
+    pub reward_rate: u64,   //8, daily MEDAL of the bear when rented
+}
+
+impl RentalListing {
+    pub fn is_settled(&self) -> bool {
+        self.claimed_until >= self.rent_end
+    }
+
+    /// MEDAL owed to the renter at `now`, with the streak but without the owner's set bonus
+    pub fn accrued_reward(&self, now: i64, schedule: &EmissionSchedule) -> Result<u64> {
+        let from = self.claimed_until.max(self.rent_start);
+        streak_accrued(
+            self.staked_time,
+            from,
+            now.min(self.rent_end),
+            |from, to| schedule.accrued(self.reward_rate, from, to),
+        )
+    }
+}
+
+/// Tiers a bear gained by burning boxes, applied to its rate every time it is staked
+#[account]
+#[derive(Default)]
+pub struct BearUpgrade {
+    //Total Size: 8 + 48 = 56
+    pub bear_mint: Pubkey,      //32
+    pub tiers: u64,             //8
+    pub last_upgrade_time: i64, //8
+}
+
+impl BearUpgrade {
+    pub fn address(bear_mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[UPGRADE_SEED.as_bytes(), bear_mint.as_ref()], &crate::ID).0
+    }
+}
+
+/// Realm the staked bears vote in and how their voting power is weighted
+#[account]
+#[derive(Default)]
+pub struct GovernanceConfig {
+    //Total Size: 8 + 88 = 96
+    pub realm: Pubkey,                //32
+    pub governing_token_mint: Pubkey, //32
+    pub duration_bonus_bps: u64,      //8, reached after duration_cap
+    pub duration_cap: i64,            //8, 0 weighs by tier only
+    pub vote_lock: i64,               //8, seconds a snapshot keeps the counted bears staked
+}
+
+/// Action a voter weight is valid for, as defined by the SPL Governance addin API
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoterWeightAction {
+    CastVote,
+    CommentProposal,
+    CreateGovernance,
+    CreateProposal,
+    SignOffProposal,
+}
+
+/// Voter weight addin record read by SPL Governance. The layout and the Anchor discriminator
+/// of the name follow `spl-governance-addin-api`, so the struct must not be renamed.
+#[account]
+#[derive(Default)]
+pub struct VoterWeightRecord {
+    //Total Size: 8 + 156 = 164
+    pub realm: Pubkey,                            //32
+    pub governing_token_mint: Pubkey,             //32
+    pub governing_token_owner: Pubkey,            //32
+    pub voter_weight: u64,                        //8
+    pub voter_weight_expiry: Option<u64>,         //1 + 8, slot of the snapshot
+    pub weight_action: Option<VoterWeightAction>, //1 + 1
+    pub weight_action_target: Option<Pubkey>,     //1 + 32
+    pub reserved: [u8; 8],                        //8
+}
+
+/// Set bonuses of the main pool, checked against a user pool whenever it stakes or unstakes
+#[account]
+#[derive(Default)]
+pub struct SetBonusConfig {
+    //Total Size: 8 + 192 = 200
+    pub bonuses: [SetBonus; MAX_SET_BONUSES], //24 * 8
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct SetBonus {
+    pub tier_mask: u64, //8, one bear of each tier with its bit set in BEAR_TIER_RATES
+    pub min_pairs: u64, //8, bears staked together with a box
+    pub bonus_bps: u64, //8, 0 for an unused slot
+}
+
+impl SetBonusConfig {
+    /// Sum of the bonuses of every set `user_pool` holds right now
+    pub fn bonus_bps(&self, user_pool: &UserPool) -> u64 {
+        let mut tiers: u64 = 0;
+        let mut pairs: u64 = 0;
+        for staked in user_pool.staked_nfts[..user_pool.staked_count as usize].iter() {
+            if let Some(tier) = bear_tier(staked) {
+                tiers |= 1 << tier;
+            }
+            if staked.box_id != 0 {
+                pairs += 1;
+            }
+        }
+
+        self.bonuses
+            .iter()
+            .filter(|b| {
+                b.bonus_bps != 0 && tiers & b.tier_mask == b.tier_mask && pairs >= b.min_pairs
+            })
+            .map(|b| b.bonus_bps)
+            .sum()
+    }
+}
+
+/// Top stakers of a pool by points. Pool 0 ranks the main pool.
+#[account]
+#[derive(Default)]
+pub struct Leaderboard {
+    //Total Size: 8 + 1448 = 1456
+    pub pool_id: u64,                                  //8
+    pub entries: [LeaderboardEntry; LEADERBOARD_SIZE], //72 * 20, highest points first
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct LeaderboardEntry {
+    pub user_pool: Pubkey, //32
+    pub owner: Pubkey,     //32, default for an unused slot
+    pub points: u64,       //8
+}
+
+impl Leaderboard {
+    /// Set `user_pool` to `points`, taking the lowest entry when it is not ranked yet.
+    /// An owner can hold several user pools in one pool, so each is ranked on its own.
+    pub fn record(&mut self, user_pool: Pubkey, owner: Pubkey, points: u64) {
+        let last = LEADERBOARD_SIZE - 1;
+        let mut index = match self.entries.iter().position(|e| e.user_pool == user_pool) {
+            Some(index) => index,
+            None if self.entries[last].points < points => last,
+            None => return,
+        };
+        self.entries[index] = LeaderboardEntry {
+            user_pool,
+            owner,
+            points,
+        };
+        while index > 0 && self.entries[index - 1].points < points {
+            self.entries.swap(index - 1, index);
+            index -= 1;
+        }
+        while index < last && self.entries[index + 1].points > points {
+            self.entries.swap(index, index + 1);
+            index += 1;
+        }
+    }
 }
 
 #[zero_copy]
 #[derive(Default)]
 pub struct StakedData {
-    pub bear_mint: Pubkey, // 32
-    pub bear_id: u64,      // 8
-    pub box_mint: Pubkey,  // 32
-    pub box_id: u64,       // 8
-    pub staked_time: i64,  // 8
+    pub bear_mint: Pubkey,  // 32
+    pub bear_id: u64,       // 8
+    pub box_mint: Pubkey,   // 32
+    pub box_id: u64,        // 8
+    pub staked_time: i64,   // 8
+    pub reward_rate: u64,   // 8
+    pub rent_start: i64,    // 8
+    pub rent_end: i64,      // 8, rewards before it go to the renter
+    pub reserved: [u64; 6], // 48
+}
+
+impl StakedData {
+    /// Streak of the bear at `now`. Unstaking drops the entry, so a restake starts over.
+    pub fn streak(&self, now: i64) -> StreakView {
+        let bonus_bps = streak_bonus_bps(self.staked_time, now);
+        let next = STREAK_TIERS
+            .iter()
+            .find(|(after, _)| now - self.staked_time < *after);
+        StreakView {
+            staked_time: self.staked_time,
+            bonus_bps,
+            next_bonus_bps: next.map_or(bonus_bps, |(_, bps)| *bps),
+            next_bonus_time: next.map_or(0, |(after, _)| self.staked_time + after),
+        }
+    }
 }
 
 #[account(zero_copy)]
 pub struct UserPool {
-    //Total Size: 8 + 2704
-    pub owner: Pubkey,                              // 32
-    pub last_claimed_time: i64,                     // 8
-    pub pending_reward: u64,                        // 8
-    pub staked_count: u64,                          // 8
-    pub mission_completed: bool,                    // 8
-    pub staked_nfts: [StakedData; STAKE_MAX_COUNT], // 88 * 30
+    //Total Size: 8 + 5264
+    pub owner: Pubkey,                                     // 32
+    pub version: u8,                                       // 8
+    pub pool_id: u64,                                      // 8
+    pub last_claimed_time: i64,                            // 8
+    pub pending_reward: u64,                               // 8
+    pub staked_count: u64,                                 // 8
+    pub mission_completed: bool,                           // 8
+    pub claim_delegate: Pubkey,                            // 32
+    pub reward_destination: Pubkey,                        // 32
+    pub extra_last_claimed_time: [i64; MAX_REWARD_TOKENS], // 8 * 4
+    pub extra_pending_reward: [u64; MAX_REWARD_TOKENS],    // 8 * 4
+    pub points: u64,                                       // 8
+    pub set_bonus_bps: u64,                                // 8
+    pub reserved: [u64; 30],                               // 240
+    pub staked_nfts: [StakedData; STAKE_MAX_COUNT],        // 160 * 30
 }
 
 impl Default for UserPool {
@@ -38,10 +497,19 @@ impl Default for UserPool {
     fn default() -> UserPool {
         UserPool {
             owner: Pubkey::default(),
+            version: USER_POOL_VERSION,
+            pool_id: 0,
             last_claimed_time: 0,
             pending_reward: 0,
             staked_count: 0,
             mission_completed: false,
+            claim_delegate: Pubkey::default(),
+            reward_destination: Pubkey::default(),
+            extra_last_claimed_time: [0; MAX_REWARD_TOKENS],
+            extra_pending_reward: [0; MAX_REWARD_TOKENS],
+            points: 0,
+            set_bonus_bps: 0,
+            reserved: [0; 30],
             staked_nfts: [StakedData {
                 ..Default::default()
             }; STAKE_MAX_COUNT],
@@ -49,13 +517,113 @@ impl Default for UserPool {
     }
 }
 
+/// Staked NFT entry of a v1 user pool
+#[derive(Clone, Copy, Default)]
+pub struct StakedDataV1 {
+    pub bear_mint: Pubkey,
+    pub bear_id: u64,
+    pub box_mint: Pubkey,
+    pub box_id: u64,
+    pub staked_time: i64,
+}
+
+/// User pool as laid out before versioning, kept only to migrate old accounts
+pub struct UserPoolV1 {
+    pub owner: Pubkey,
+    pub last_claimed_time: i64,
+    pub pending_reward: u64,
+    pub staked_count: u64,
+    pub mission_completed: bool,
+    pub staked_nfts: Vec<StakedDataV1>,
+}
+
+impl UserPoolV1 {
+    /// Decode the v1 layout, packed like every Anchor 0.26 zero copy account: 57 bytes of
+    /// header, then 88 bytes per NFT. Accounts were allocated with room for a padded header,
+    /// so the last 7 bytes are unused.
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() == USER_POOL_V1_SIZE,
+            StakingError::InvalidUserPool
+        );
+        let data = &data[8..];
+        let pubkey_at = |o: usize| Pubkey::new_from_array(data[o..o + 32].try_into().unwrap());
+        let u64_at = |o: usize| u64::from_le_bytes(data[o..o + 8].try_into().unwrap());
+
+        let staked_count = u64_at(48);
+        require!(
+            staked_count as usize <= STAKE_MAX_COUNT,
+            StakingError::InvalidUserPool
+        );
+        let staked_nfts = (0..staked_count as usize)
+            .map(|i| {
+                let o = 57 + i * 88;
+                StakedDataV1 {
+                    bear_mint: pubkey_at(o),
+                    bear_id: u64_at(o + 32),
+                    box_mint: pubkey_at(o + 40),
+                    box_id: u64_at(o + 72),
+                    staked_time: u64_at(o + 80) as i64,
+                }
+            })
+            .collect();
+
+        Ok(UserPoolV1 {
+            owner: pubkey_at(0),
+            last_claimed_time: u64_at(32) as i64,
+            pending_reward: u64_at(40),
+            staked_count,
+            mission_completed: data[56] != 0,
+            staked_nfts,
+        })
+    }
+}
+
 impl UserPool {
+    /// Rebuild a v1 pool in the current layout on top of zeroed account data. Legacy NFTs
+    /// keep a zero `reward_rate` so the reward engine falls back to the bear id ranges.
+    pub fn migrate_from(&mut self, v1: &UserPoolV1) {
+        self.version = USER_POOL_VERSION;
+        self.owner = v1.owner;
+        self.last_claimed_time = v1.last_claimed_time;
+        self.pending_reward = v1.pending_reward;
+        self.mission_completed = v1.mission_completed;
+        for staked in v1.staked_nfts.iter() {
+            self.add_nft(
+                staked.bear_mint,
+                staked.bear_id,
+                staked.box_mint,
+                staked.box_id,
+                0,
+                staked.staked_time,
+            );
+        }
+    }
+
+    pub fn can_claim(&self, claimer: &Pubkey) -> bool {
+        self.owner == *claimer
+            || (self.claim_delegate != Pubkey::default() && self.claim_delegate == *claimer)
+    }
+
+    pub fn reward_destination(&self) -> Pubkey {
+        if self.reward_destination == Pubkey::default() {
+            self.owner
+        } else {
+            self.reward_destination
+        }
+    }
+
+    pub fn find_nft(&self, bear_nft: &Pubkey) -> Option<usize> {
+        (0..self.staked_count as usize).find(|&i| self.staked_nfts[i].bear_mint == *bear_nft)
+    }
+
     pub fn add_nft(
         &mut self,
         bear_nft: Pubkey,
         bear_id: u64,
         box_nft: Pubkey,
         box_id: u64,
+        reward_rate: u64,
         now: i64,
     ) {
         let idx = self.staked_count as usize;
@@ -64,10 +632,153 @@ impl UserPool {
         self.staked_nfts[idx].box_mint = box_nft;
         self.staked_nfts[idx].box_id = box_id;
         self.staked_nfts[idx].staked_time = now;
+        self.staked_nfts[idx].reward_rate = reward_rate;
+        self.staked_nfts[idx].rent_start = 0;
+        self.staked_nfts[idx].rent_end = 0;
         self.staked_count += 1;
     }
 
-    pub fn remove_nft(&mut self, bear_nft: Pubkey, now: i64) -> Result<()> {
+    pub fn accrued_reward(
+        &self,
+        index: usize,
+        now: i64,
+        schedule: &EmissionSchedule,
+    ) -> Result<u64> {
+        let staked = self.staked_nfts[index];
+        let reward_rate = staked_reward_rate(&staked)?;
+
+        let mut last_claimed_time: i64 = self.last_claimed_time;
+        if last_claimed_time < staked.staked_time {
+            last_claimed_time = staked.staked_time;
+        }
+
+        let reward = owner_accrued(&staked, last_claimed_time, now, |from, to| {
+            schedule.accrued(reward_rate, from, to)
+        })?;
+        apply_bonus(reward, self.set_bonus_bps)
+    }
+
+    pub fn total_accrued_reward(&self, now: i64, schedule: &EmissionSchedule) -> Result<u64> {
+        let mut total_reward: u64 = 0;
+        for i in 0..self.staked_count {
+            let reward = self.accrued_reward(i as usize, now, schedule)?;
+            total_reward = total_reward
+                .checked_add(reward)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+        Ok(total_reward)
+    }
+
+    /// Points of a staked NFT since the last claim. They follow its reward rate and streak
+    /// without the emission decay, so tier, box and time staked weigh the same all season long.
+    pub fn accrued_points(&self, index: usize, now: i64) -> Result<u64> {
+        let staked = self.staked_nfts[index];
+        let reward_rate = staked_reward_rate(&staked)?;
+        let last_claimed_time = self.last_claimed_time.max(staked.staked_time);
+        streak_accrued(staked.staked_time, last_claimed_time, now, |from, to| {
+            linear_accrued(reward_rate, from, to)
+        })
+    }
+
+    /// Add the points of every staked NFT up to `now`, before `last_claimed_time` moves
+    pub fn settle_points(&mut self, now: i64) -> Result<()> {
+        for i in 0..self.staked_count {
+            let points = self.accrued_points(i as usize, now)?;
+            self.points = self
+                .points
+                .checked_add(points)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// Park everything accrued so far at the current set bonus and switch to `bonus_bps`
+    pub fn update_set_bonus(
+        &mut self,
+        bonus_bps: u64,
+        now: i64,
+        schedule: &EmissionSchedule,
+    ) -> Result<()> {
+        if bonus_bps == self.set_bonus_bps {
+            return Ok(());
+        }
+        self.settle_reward(now, schedule)?;
+        self.set_bonus_bps = bonus_bps;
+        Ok(())
+    }
+
+    /// Park everything accrued up to `now` as pending, before the accrual rules change
+    pub fn settle_reward(&mut self, now: i64, schedule: &EmissionSchedule) -> Result<()> {
+        if now > self.last_claimed_time {
+            self.pending_reward = self
+                .pending_reward
+                .checked_add(self.total_accrued_reward(now, schedule)?)
+                .ok_or(StakingError::MathOverflow)?;
+            self.settle_points(now)?;
+            self.last_claimed_time = now;
+        }
+        Ok(())
+    }
+
+    /// Take the pending reward plus, once a day, everything accrued since the last claim
+    pub fn claim_reward(&mut self, now: i64, schedule: &EmissionSchedule) -> Result<u64> {
+        let mut total_reward = self.pending_reward;
+        if now - self.last_claimed_time >= ONE_DAY {
+            total_reward = total_reward
+                .checked_add(self.total_accrued_reward(now, schedule)?)
+                .ok_or(StakingError::MathOverflow)?;
+            self.settle_points(now)?;
+            self.last_claimed_time = now;
+        }
+        self.pending_reward = 0;
+        Ok(total_reward)
+    }
+
+    pub fn extra_accrued_reward(
+        &self,
+        index: usize,
+        slot: usize,
+        config: &RewardTokenConfig,
+        now: i64,
+    ) -> Result<u64> {
+        let staked = self.staked_nfts[index];
+        let medal_rate = staked_reward_rate(&staked)?;
+
+        let last_claimed_time = self.extra_last_claimed_time[slot]
+            .max(staked.staked_time)
+            .max(config.start_time);
+
+        streak_accrued(staked.staked_time, last_claimed_time, now, |from, to| {
+            config.accrued(medal_rate, from, to)
+        })
+    }
+
+    /// Settle a co-sponsored reward token up to `now` and take everything owed for it
+    pub fn claim_extra_reward(
+        &mut self,
+        slot: usize,
+        config: &RewardTokenConfig,
+        now: i64,
+    ) -> Result<u64> {
+        let mut total_reward = self.extra_pending_reward[slot];
+        for i in 0..self.staked_count {
+            let reward = self.extra_accrued_reward(i as usize, slot, config, now)?;
+            total_reward = total_reward
+                .checked_add(reward)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+        self.extra_last_claimed_time[slot] = now;
+        self.extra_pending_reward[slot] = 0;
+        Ok(total_reward)
+    }
+
+    pub fn remove_nft(
+        &mut self,
+        bear_nft: Pubkey,
+        now: i64,
+        schedule: &EmissionSchedule,
+        reward_tokens: &[RewardTokenConfig],
+    ) -> Result<()> {
         let mut withdrawn: u8 = 0;
         let mut index: usize = 0;
         // Find NFT in pool
@@ -80,69 +791,26 @@ impl UserPool {
             }
         }
         require!(withdrawn == 1, StakingError::InvalidNftAddress);
+        require!(
+            now >= self.staked_nfts[index].rent_end,
+            StakingError::BearRented
+        );
 
-        let reward: u64;
-        let mut reward_rate: u64;
-
-        match self.staked_nfts[index].bear_id {
-            1..=1920 | 8001..=8480 => {
-                reward_rate = 10;
-            }
-            1921..=3520 | 8481..=8880 => {
-                reward_rate = 14;
-            }
-            3521..=4880 | 8881..=9220 => {
-                reward_rate = 17;
-            }
-            4881..=6000 | 9221..=9500 => {
-                reward_rate = 20;
-            }
-            6001..=6880 | 9501..=9720 => {
-                reward_rate = 22;
-            }
-            6881..=7520 | 9721..=9880 => {
-                reward_rate = 24;
-            }
-            7521..=7920 | 9881..=9980 => {
-                reward_rate = 26;
-            }
-            7921..=7992 | 9981..=9998 => {
-                reward_rate = 30;
-            }
-            7993..=8000 | 9999..=10000 => {
-                reward_rate = 100;
-            }
-            _ => {
-                return Err(StakingError::NotAllowedNFTID.into());
-            }
-        }
+        let reward = self.accrued_reward(index, now, schedule)?;
+        self.pending_reward += reward;
+        let points = self.accrued_points(index, now)?;
+        self.points = self
+            .points
+            .checked_add(points)
+            .ok_or(StakingError::MathOverflow)?;
 
-        match self.staked_nfts[index].box_id {
-            1..=5000 => {
-                reward_rate *= 1_100_000_000;
-            }
-            5001..=8000 => {
-                reward_rate *= 1_200_000_000;
-            }
-            8001..=9500 => {
-                reward_rate *= 1_300_000_000;
-            }
-            9501..=10000 => {
-                reward_rate *= 1_500_000_000;
+        for (slot, config) in reward_tokens.iter().enumerate() {
+            if config.mint != Pubkey::default() {
+                let reward = self.extra_accrued_reward(index, slot, config, now)?;
+                self.extra_pending_reward[slot] += reward;
             }
-            _ => {
-                reward_rate *= 1_000_000_000;
-            }
-        }
-
-        let mut last_claimed_time: i64 = self.last_claimed_time;
-        if last_claimed_time < self.staked_nfts[index].staked_time {
-            last_claimed_time = self.staked_nfts[index].staked_time;
         }
 
-        reward = (((now - last_claimed_time) / ONE_DAY) as u64) * reward_rate;
-        self.pending_reward += reward;
-
         // Remove NFT from pool
         let last_idx: usize = (self.staked_count - 1) as usize;
         if index != last_idx {
@@ -1,7 +1,38 @@
 pub const GLOBAL_AUTHORITY_SEED: &str = "global-authority";
 pub const SOL_VAULT_SEED: &str = "sol-vault";
+pub const VESTING_SEED: &str = "vesting";
+pub const POOL_SEED: &str = "pool";
+pub const LEADERBOARD_SEED: &str = "leaderboard";
+pub const SET_BONUS_SEED: &str = "set-bonus";
+pub const RECEIPT_SEED: &str = "receipt";
+pub const STAKE_RECORD_SEED: &str = "stake-record";
+pub const GOVERNANCE_SEED: &str = "governance";
+pub const VOTER_WEIGHT_SEED: &str = "voter-weight-record";
+pub const RENTAL_SEED: &str = "rental";
+pub const UPGRADE_SEED: &str = "upgrade";
+pub const RECEIPT_SYMBOL: &str = "sBEAR";
 pub const ONE_DAY: i64 = 86400;
 pub const STAKE_MAX_COUNT: usize = 30;
+pub const MAX_REWARD_TOKENS: usize = 4;
+pub const MAX_RATE_CHANGES: usize = 4;
+pub const LEADERBOARD_SIZE: usize = 20;
+pub const MAX_SET_BONUSES: usize = 8;
+pub const MAX_VESTING_TRANCHES: usize = 16;
+pub const MAX_RENTAL_DURATION: i64 = 365 * ONE_DAY;
+/// Daily MEDAL of each bear tier, the bit of a tier in `SetBonus::tier_mask` is its index
+pub const BEAR_TIER_RATES: [u64; 9] = [10, 14, 17, 20, 22, 24, 26, 30, 100];
+/// Highest tier in `BEAR_TIER_RATES` a box upgrade reaches, the 1/1 tier stays out of reach
+pub const MAX_UPGRADE_TIER: usize = 7;
+pub const MISSION_BONUS: u64 = 25_000_000_000;
+pub const USER_POOL_VERSION: u8 = 2;
+/// Size of the global pool before the emission schedule, super admin and staked count only
+pub const GLOBAL_POOL_V1_SIZE: usize = 48;
+/// Size v1 user pools were allocated at, the packed v1 struct only fills the first 8 + 2697 bytes
+pub const USER_POOL_V1_SIZE: usize = 2712;
 pub const BEAR_COLLECTION_ADDRESS: &str = "4qcUmR2ms2Z6EuPw2kpk3G7ZG9vTwhb1K2sDpAjeSBuL";
 pub const BOX_COLLECTION_ADDRESS: &str = "4eFgfG6YwrhtXjoY5PFm1zAvtqMb6JqCx5tZUDerNBAf";
 pub const MEDAL_TOKEN_ADDRESS: &str = "3BAfTyeyPkykQuC5g1FejbebcphhWTBgEwJ75XXBW6CW";
+pub const EMISSION_PRECISION: u128 = 1_000_000_000;
+pub const BPS_DENOMINATOR: u128 = 10_000;
+/// Streak tiers as (time continuously staked, bonus in bps), lowest first
+pub const STREAK_TIERS: [(i64, u64); 3] = [(0, 0), (30 * ONE_DAY, 500), (90 * ONE_DAY, 1_000)];
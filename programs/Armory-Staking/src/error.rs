@@ -18,4 +18,70 @@ pub enum StakingError {
     InvalidClaimRequest,
     #[msg("There isn't such a amount of token in the vault")]
     InsufficientRewardVault,
+    #[msg("Invalid super admin")]
+    InvalidSuperAdmin,
+    #[msg("Invalid emission schedule")]
+    InvalidEmissionSchedule,
+    #[msg("Emission schedule already started or starts in the past")]
+    EmissionScheduleStarted,
+    #[msg("Reward calculation overflowed")]
+    MathOverflow,
+    #[msg("Invalid rarity proof")]
+    InvalidRarityProof,
+    #[msg("Not allowed to claim for this UserPool")]
+    InvalidClaimAuthority,
+    #[msg("Reward account is not owned by the reward destination")]
+    InvalidRewardDestination,
+    #[msg("Invalid vesting config")]
+    InvalidVestingConfig,
+    #[msg("Vesting schedule is required to claim")]
+    MissingVestingSchedule,
+    #[msg("Nothing to withdraw")]
+    NothingToWithdraw,
+    #[msg("There isn't such a amount of SOL in the vault")]
+    InsufficientSolVault,
+    #[msg("Invalid or unregistered reward token")]
+    InvalidRewardToken,
+    #[msg("No free reward token slot")]
+    RewardTokenSlotsFull,
+    #[msg("Invalid pool config")]
+    InvalidPoolConfig,
+    #[msg("Invalid pool admin")]
+    InvalidPoolAdmin,
+    #[msg("The pool is not open for staking")]
+    PoolNotActive,
+    #[msg("Too many NFTs staked in this UserPool")]
+    UserPoolFull,
+    #[msg("UserPool must be migrated to the current version")]
+    UserPoolNeedsMigration,
+    #[msg("Leaderboard belongs to another pool")]
+    InvalidLeaderboard,
+    #[msg("Invalid set bonus")]
+    InvalidSetBonus,
+    #[msg("Accounts do not match the stake receipt")]
+    InvalidReceipt,
+    #[msg("Invalid voting weights")]
+    InvalidVotingWeights,
+    #[msg("Voter weight record does not belong to the signer")]
+    InvalidVoter,
+    #[msg("Bear is not available for rent")]
+    RentalUnavailable,
+    #[msg("Bear is rented out")]
+    BearRented,
+    #[msg("Invalid rental payment accounts")]
+    InvalidRentalPayment,
+    #[msg("Rental duration must be positive and at most a year")]
+    InvalidRentalDuration,
+    #[msg("Bear cannot be upgraded further")]
+    UpgradeMaxed,
+    #[msg("Asset id does not match the merkle tree leaf")]
+    InvalidAssetId,
+    #[msg("Reward token rate changed too often, add a new reward token instead")]
+    RateHistoryFull,
+    #[msg("Bear is locked by a recent vote")]
+    VoteLocked,
+    #[msg("Stake records must be sorted and belong to the voter")]
+    InvalidStakeRecords,
+    #[msg("Global pool is not in the layout before the emission schedule")]
+    InvalidGlobalPool,
 }
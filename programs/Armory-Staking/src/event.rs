@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct RewardShortfall {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub claimed: u64,
+    pub shortfall: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub vested: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptMinted {
+    pub owner: Pubkey,
+    pub bear_mint: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReceiptRedeemed {
+    pub holder: Pubkey,
+    pub bear_mint: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VotingPowerSnapshot {
+    pub owner: Pubkey,
+    pub voter_weight: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NftRented {
+    pub owner: Pubkey,
+    pub renter: Pubkey,
+    pub bear_mint: Pubkey,
+    pub price: u64,
+    pub pay_in_medal: bool,
+    pub rent_end: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BearUpgraded {
+    pub owner: Pubkey,
+    pub bear_mint: Pubkey,
+    pub box_mint: Pubkey,
+    pub box_id: u64,
+    pub tiers: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MissionCompleted {
+    pub owner: Pubkey,
+    pub bonus: u64,
+    pub timestamp: i64,
+}
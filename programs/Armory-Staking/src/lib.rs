@@ -1,15 +1,25 @@
-use anchor_lang::{prelude::*, AccountSerialize};
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::{prelude::*, system_program, AccountSerialize, Discriminator};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use mpl_bubblegum::{program::Bubblegum, state::metaplex_adapter::MetadataArgs};
 use mpl_token_metadata::state::{Metadata, TokenMetadataAccount};
-use solana_program::pubkey::Pubkey;
+use solana_program::{
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+};
+use spl_account_compression::{program::SplAccountCompression, Noop};
 
 pub mod account;
 pub mod constant;
 pub mod error;
+pub mod event;
+pub mod reward;
 
 use account::*;
 use constant::*;
 use error::*;
+use event::*;
+use reward::*;
 
 declare_id!("GqVfxjhCXWvhQtMg9x2K2BqhRDdC35MXxDjbLVdhaDv2");
 
@@ -21,78 +31,406 @@ pub mod armory_staking {
         let global_authority = &mut ctx.accounts.global_authority;
         global_authority.super_admin = ctx.accounts.admin.key();
 
+        // Fees below the rent exempt minimum could never land in an empty vault
+        let rent_exempt = ctx.accounts.rent.minimum_balance(0);
+        let shortfall = rent_exempt.saturating_sub(ctx.accounts.sol_vault.lamports());
+        if shortfall > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.admin.to_account_info(),
+                to: ctx.accounts.sol_vault.to_account_info(),
+            };
+            system_program::transfer(
+                CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts),
+                shortfall,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Grow a global pool created before the emission schedule to the current layout. Everything
+    /// added since starts zeroed: no decay, no rarity root, no vesting, fees or reward tokens.
+    /// From then on rewards accrue per second instead of per whole day staked.
+    pub fn migrate_global_pool(ctx: Context<MigrateGlobalPool>) -> Result<()> {
+        let global_info = ctx.accounts.global_authority.to_account_info();
+        {
+            let data = global_info.try_borrow_data()?;
+            require!(
+                *global_info.owner == crate::ID
+                    && data.len() == GLOBAL_POOL_V1_SIZE
+                    && data[..8] == GlobalPool::discriminator(),
+                StakingError::InvalidGlobalPool
+            );
+            require!(
+                data[8..40] == ctx.accounts.admin.key().to_bytes(),
+                StakingError::InvalidSuperAdmin
+            );
+        }
+
+        let rent_exempt = Rent::get()?.minimum_balance(GlobalPool::SIZE);
+        let top_up = rent_exempt.saturating_sub(global_info.lamports());
+        if top_up > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.admin.to_account_info(),
+                to: global_info.clone(),
+            };
+            system_program::transfer(
+                CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts),
+                top_up,
+            )?;
+        }
+        global_info.realloc(GlobalPool::SIZE, false)?;
+        global_info.try_borrow_mut_data()?[GLOBAL_POOL_V1_SIZE..].fill(0);
+
+        let mut global_authority = Account::<GlobalPool>::try_from(&global_info)?;
+        global_authority.emission = EmissionSchedule::default();
+        global_authority.exit(&crate::ID)?;
+        msg!(
+            "Migrated the global pool with {} staked NFTs",
+            global_authority.total_staked_count
+        );
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn set_emission_schedule(
+        ctx: Context<SetEmissionSchedule>,
+        start_time: i64,
+        epoch_length: i64,
+        decay_bps: u64,
+    ) -> Result<()> {
+        require!(
+            epoch_length >= 0 && decay_bps as u128 <= BPS_DENOMINATOR,
+            StakingError::InvalidEmissionSchedule
+        );
+        let global_authority = &mut ctx.accounts.global_authority;
+        let emission = EmissionSchedule {
+            start_time,
+            epoch_length,
+            decay_bps,
+        };
+        require!(
+            global_authority
+                .emission
+                .can_replace(&emission, Clock::get()?.unix_timestamp),
+            StakingError::EmissionScheduleStarted
+        );
+        global_authority.emission = emission;
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn set_rarity_root(ctx: Context<SetRarityRoot>, rarity_root: [u8; 32]) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
+        global_authority.rarity_root = rarity_root;
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn set_vesting_config(
+        ctx: Context<SetVestingConfig>,
+        vesting_duration: i64,
+        instant_haircut_bps: u64,
+    ) -> Result<()> {
+        require!(
+            vesting_duration >= 0 && instant_haircut_bps as u128 <= BPS_DENOMINATOR,
+            StakingError::InvalidVestingConfig
+        );
+        let global_authority = &mut ctx.accounts.global_authority;
+        global_authority.vesting_duration = vesting_duration;
+        global_authority.instant_haircut_bps = instant_haircut_bps;
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        stake_fee: u64,
+        unstake_fee: u64,
+        claim_fee: u64,
+    ) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
+        global_authority.stake_fee = stake_fee;
+        global_authority.unstake_fee = unstake_fee;
+        global_authority.claim_fee = claim_fee;
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn withdraw_sol_fees(
+        ctx: Context<WithdrawSolFees>,
+        _vault_bump: u8,
+        amount: u64,
+    ) -> Result<()> {
+        // Keep the vault rent exempt so it can keep receiving fees
+        let rent_exempt = Rent::get()?.minimum_balance(0);
+        let required = amount
+            .checked_add(rent_exempt)
+            .ok_or(StakingError::MathOverflow)?;
+        require!(
+            ctx.accounts.sol_vault.lamports() >= required,
+            StakingError::InsufficientSolVault
+        );
+
+        let seeds = &[SOL_VAULT_SEED.as_bytes(), &[_vault_bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = system_program::Transfer {
+            from: ctx.accounts.sol_vault.to_account_info(),
+            to: ctx.accounts.admin.to_account_info(),
+        };
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn add_reward_token(ctx: Context<AddRewardToken>, rate_per_medal: u64) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
+        let mint = ctx.accounts.reward_mint.key();
+        require!(
+            mint != MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap()
+                && global_authority
+                    .reward_tokens
+                    .iter()
+                    .all(|t| t.mint != mint),
+            StakingError::InvalidRewardToken
+        );
+
+        let slot = global_authority
+            .reward_tokens
+            .iter()
+            .position(|t| t.mint == Pubkey::default())
+            .ok_or(StakingError::RewardTokenSlotsFull)?;
+        global_authority.reward_tokens[slot] = RewardTokenConfig {
+            mint,
+            rate_per_medal,
+            start_time: Clock::get()?.unix_timestamp,
+            previous_rates: Default::default(),
+        };
+        msg!("Reward Token Slot: {}", slot);
+
+        Ok(())
+    }
+
+    /// The new rate applies from now on, rewards accrued before keep the old one
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn set_reward_token_rate(
+        ctx: Context<SetRewardTokenRate>,
+        slot: u8,
+        rate_per_medal: u64,
+    ) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
+        let config = global_authority
+            .reward_tokens
+            .get_mut(slot as usize)
+            .filter(|t| t.mint != Pubkey::default())
+            .ok_or(StakingError::InvalidRewardToken)?;
+        config.set_rate(rate_per_medal, Clock::get()?.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn init_set_bonus_config(ctx: Context<InitSetBonusConfig>) -> Result<()> {
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn init_governance(
+        ctx: Context<InitGovernance>,
+        realm: Pubkey,
+        governing_token_mint: Pubkey,
+        duration_bonus_bps: u64,
+        duration_cap: i64,
+        vote_lock: i64,
+    ) -> Result<()> {
+        require!(
+            duration_bonus_bps as u128 <= BPS_DENOMINATOR && duration_cap >= 0 && vote_lock >= 0,
+            StakingError::InvalidVotingWeights
+        );
+        let governance_config = &mut ctx.accounts.governance_config;
+        governance_config.realm = realm;
+        governance_config.governing_token_mint = governing_token_mint;
+        governance_config.duration_bonus_bps = duration_bonus_bps;
+        governance_config.duration_cap = duration_cap;
+        governance_config.vote_lock = vote_lock;
+        Ok(())
+    }
+
+    /// Voters get the new weights with their next snapshot
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn set_voting_weights(
+        ctx: Context<SetVotingWeights>,
+        duration_bonus_bps: u64,
+        duration_cap: i64,
+        vote_lock: i64,
+    ) -> Result<()> {
+        require!(
+            duration_bonus_bps as u128 <= BPS_DENOMINATOR && duration_cap >= 0 && vote_lock >= 0,
+            StakingError::InvalidVotingWeights
+        );
+        let governance_config = &mut ctx.accounts.governance_config;
+        governance_config.duration_bonus_bps = duration_bonus_bps;
+        governance_config.duration_cap = duration_cap;
+        governance_config.vote_lock = vote_lock;
+        Ok(())
+    }
+
+    /// Stakers get the new bonus the next time they stake or unstake
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn set_set_bonus(
+        ctx: Context<SetSetBonus>,
+        slot: u8,
+        tier_mask: u64,
+        min_pairs: u64,
+        bonus_bps: u64,
+    ) -> Result<()> {
+        require!(
+            tier_mask >> BEAR_TIER_RATES.len() == 0
+                && min_pairs <= STAKE_MAX_COUNT as u64
+                && (tier_mask != 0 || min_pairs != 0 || bonus_bps == 0)
+                && bonus_bps as u128 <= BPS_DENOMINATOR,
+            StakingError::InvalidSetBonus
+        );
+        let set_bonus = ctx
+            .accounts
+            .set_bonus_config
+            .bonuses
+            .get_mut(slot as usize)
+            .ok_or(StakingError::InvalidSetBonus)?;
+        *set_bonus = SetBonus {
+            tier_mask,
+            min_pairs,
+            bonus_bps,
+        };
+
         Ok(())
     }
 
     pub fn init_user_pool(ctx: Context<InitUserPool>) -> Result<()> {
         let mut user_pool = ctx.accounts.user_pool.load_init()?;
         user_pool.owner = ctx.accounts.owner.key();
+        user_pool.version = USER_POOL_VERSION;
         msg!("Owner: {:?}", user_pool.owner.to_string());
 
         Ok(())
     }
 
-    #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
-    pub fn stake_nft(ctx: Context<StakeNft>, _global_bump: u8, _box_id: u64) -> Result<()> {
-        let global_authority = &mut ctx.accounts.global_authority;
-        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
-        let mint_metadata = &mut &ctx.accounts.mint_metadata;
-
-        msg!("Metadata Account: {:?}", ctx.accounts.mint_metadata.key());
-        let (metadata, _) = Pubkey::find_program_address(
-            &[
-                mpl_token_metadata::state::PREFIX.as_bytes(),
-                mpl_token_metadata::id().as_ref(),
-                ctx.accounts.nft_mint.key().as_ref(),
-            ],
-            &mpl_token_metadata::id(),
-        );
+    pub fn migrate_user_pool(ctx: Context<MigrateUserPool>) -> Result<()> {
+        let user_pool_info = ctx.accounts.user_pool.to_account_info();
         require!(
-            metadata == mint_metadata.key(),
-            StakingError::InvalidMetadata
+            *user_pool_info.owner == crate::ID,
+            StakingError::InvalidUserPool
         );
 
-        let nft_metadata = Metadata::from_account_info(mint_metadata)?;
+        let v1 = {
+            let data = user_pool_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == UserPool::discriminator(),
+                StakingError::InvalidUserPool
+            );
+            UserPoolV1::unpack(&data)?
+        };
+        require!(
+            v1.owner == ctx.accounts.owner.key(),
+            StakingError::InvalidUserPool
+        );
 
-        if let Some(creators) = nft_metadata.data.creators {
-            let mut valid: u8 = 0;
-            let mut collection: Pubkey = Pubkey::default();
-            for creator in creators {
-                if creator.address.to_string() == BEAR_COLLECTION_ADDRESS && creator.verified {
-                    valid = 1;
-                    collection = creator.address;
-                    break;
-                }
-            }
-            require!(valid == 1, StakingError::UnkownOrNotAllowedNFTCollection);
-            msg!("Collection= {:?}", collection);
-        } else {
-            return Err(Error::from(StakingError::MetadataCreatorParseError));
+        // Top up rent for the larger layout, then grow and clear everything past the discriminator
+        let new_len = 8 + std::mem::size_of::<UserPool>();
+        let rent_exempt = Rent::get()?.minimum_balance(new_len);
+        let top_up = rent_exempt.saturating_sub(user_pool_info.lamports());
+        if top_up > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.owner.to_account_info(),
+                to: user_pool_info.clone(),
+            };
+            system_program::transfer(
+                CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts),
+                top_up,
+            )?;
         }
+        user_pool_info.realloc(new_len, false)?;
+        user_pool_info.try_borrow_mut_data()?[8..].fill(0);
+
+        let user_pool_loader = AccountLoader::<UserPool>::try_from(&user_pool_info)?;
+        let mut user_pool = user_pool_loader.load_mut()?;
+        user_pool.migrate_from(&v1);
+        msg!(
+            "Migrated {} staked NFTs to v{}",
+            v1.staked_count,
+            USER_POOL_VERSION
+        );
 
-        let char_vec: Vec<char> = nft_metadata.data.name.chars().collect();
-        let mut num_array = vec![];
-        let mut idx = 0;
-        let mut index = 10000;
-        while idx < char_vec.len() - 1 {
-            if char_vec[idx] == '#' {
-                index = idx;
-            }
-            idx += 1;
-            if index != 10000 {
-                if u32::from(char_vec[idx]) == 0 {
-                    break;
-                }
-                num_array.push(char_vec[idx]);
-            }
-        }
+        Ok(())
+    }
 
-        let nft_id: String = num_array
-            .into_iter()
-            .map(|i| i.to_string())
-            .collect::<String>();
-        let id = nft_id.parse::<u64>().unwrap();
-        msg!("NFT ID: {}", id);
+    /// Create the owner's vesting schedule, vested claims need it before the first one
+    pub fn init_vesting(ctx: Context<InitVesting>) -> Result<()> {
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.owner = ctx.accounts.owner.key();
+
+        Ok(())
+    }
+
+    #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
+    pub fn set_claim_config(
+        ctx: Context<SetClaimConfig>,
+        claim_delegate: Pubkey,
+        reward_destination: Pubkey,
+    ) -> Result<()> {
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        user_pool.claim_delegate = claim_delegate;
+        user_pool.reward_destination = reward_destination;
+
+        Ok(())
+    }
+
+    #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
+    pub fn stake_nft(
+        ctx: Context<StakeNft>,
+        _global_bump: u8,
+        _box_id: u64,
+        rarity_rate: u64,
+        rarity_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        let mint_metadata = &ctx.accounts.mint_metadata;
+
+        collect_fee(
+            ctx.accounts.owner.to_account_info(),
+            ctx.accounts.sol_vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            global_authority.stake_fee,
+        )?;
+
+        let id = verify_nft(
+            mint_metadata,
+            &ctx.accounts.nft_mint.key(),
+            &BEAR_COLLECTION_ADDRESS.parse::<Pubkey>().unwrap(),
+        )?;
+        let reward_rate = resolve_reward_rate(
+            &global_authority.rarity_root,
+            &ctx.accounts.nft_mint.key(),
+            id,
+            rarity_rate,
+            &rarity_proof,
+        )?;
+        let reward_rate = upgraded_reward_rate(&ctx.accounts.bear_upgrade, reward_rate)?;
+        msg!("Reward Rate: {}", reward_rate);
 
         let timestamp = Clock::get()?.unix_timestamp;
         let user_bear_account = &mut &ctx.accounts.user_bear_account;
@@ -135,10 +473,15 @@ pub mod armory_staking {
             };
             token::transfer(
                 CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
-                25_000_000_000,
+                MISSION_BONUS,
             )?;
 
             user_pool.mission_completed = true;
+            emit!(MissionCompleted {
+                owner: user_pool.owner,
+                bonus: MISSION_BONUS,
+                timestamp,
+            });
         }
 
         user_pool.add_nft(
@@ -146,18 +489,37 @@ pub mod armory_staking {
             id,
             ctx.accounts.nft_box_mint.key(),
             _box_id,
+            reward_rate,
             timestamp,
         );
         global_authority.total_staked_count += 1;
+        let staked = user_pool.staked_nfts[user_pool.staked_count as usize - 1];
+        ctx.accounts
+            .stake_record
+            .record(ctx.accounts.owner.key(), 0, &staked);
+
+        let bonus_bps = set_bonus_bps(&ctx.accounts.set_bonus_config, &user_pool)?;
+        user_pool.update_set_bonus(bonus_bps, timestamp, &global_authority.emission)?;
 
         Ok(())
     }
 
     #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
-    pub fn unstake_nft(ctx: Context<UnstakeNft>, _global_bump: u8, _box_id: u64) -> Result<()> {
+    pub fn unstake_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnstakeNft<'info>>,
+        _global_bump: u8,
+        _box_id: u64,
+    ) -> Result<()> {
         let global_authority = &mut ctx.accounts.global_authority;
         let mut user_pool = ctx.accounts.user_pool.load_mut()?;
 
+        collect_fee(
+            ctx.accounts.owner.to_account_info(),
+            ctx.accounts.sol_vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            global_authority.unstake_fee,
+        )?;
+
         let timestamp = Clock::get()?.unix_timestamp;
         let user_bear_account = &mut &ctx.accounts.user_bear_account;
         let dest_bear_account = &mut &ctx.accounts.dest_bear_account;
@@ -198,98 +560,404 @@ pub mod armory_staking {
             )?;
         }
 
-        user_pool.remove_nft(ctx.accounts.nft_mint.key(), timestamp)?;
+        user_pool.remove_nft(
+            ctx.accounts.nft_mint.key(),
+            timestamp,
+            &global_authority.emission,
+            &global_authority.reward_tokens,
+        )?;
         global_authority.total_staked_count -= 1;
+        close_stake_record(
+            &ctx.accounts.stake_record,
+            &ctx.accounts.owner.to_account_info(),
+        )?;
+
+        let bonus_bps = set_bonus_bps(&ctx.accounts.set_bonus_config, &user_pool)?;
+        user_pool.update_set_bonus(bonus_bps, timestamp, &global_authority.emission)?;
+        record_points(
+            &ctx.accounts.leaderboard,
+            ctx.accounts.user_pool.key(),
+            &user_pool,
+        )?;
+
+        // Pay the parked rewards right away when the reward vault and the destination's MEDAL
+        // account follow the accounts. With vesting enabled they stay pending until claimed.
+        require!(
+            matches!(ctx.remaining_accounts.len(), 0 | 2),
+            StakingError::InvalidRewardDestination
+        );
+        if ctx.remaining_accounts.len() == 2 && global_authority.vesting_duration == 0 {
+            let reward_vault = Account::<TokenAccount>::try_from(&ctx.remaining_accounts[0])?;
+            let user_reward_account =
+                Account::<TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
+            let medal_mint = MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap();
+            require!(
+                reward_vault.mint == medal_mint && reward_vault.owner == global_authority.key(),
+                StakingError::InvalidRewardToken
+            );
+            require!(
+                user_reward_account.mint == medal_mint
+                    && user_reward_account.owner == user_pool.reward_destination(),
+                StakingError::InvalidRewardDestination
+            );
+
+            let pending_reward = user_pool.pending_reward;
+            let claimed = pending_reward.min(reward_vault.amount);
+            user_pool.pending_reward = pending_reward - claimed;
+            if claimed < pending_reward {
+                emit!(RewardShortfall {
+                    owner: user_pool.owner,
+                    mint: reward_vault.mint,
+                    claimed,
+                    shortfall: pending_reward - claimed,
+                    timestamp,
+                });
+            }
+
+            let cpi_accounts = Transfer {
+                from: reward_vault.to_account_info(),
+                to: user_reward_account.to_account_info(),
+                authority: global_authority.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.clone().to_account_info(),
+                    cpi_accounts,
+                    signer,
+                ),
+                claimed,
+            )?;
+            emit!(RewardClaimed {
+                owner: user_pool.owner,
+                mint: reward_vault.mint,
+                amount: claimed,
+                vested: false,
+                timestamp,
+            });
+        }
 
         Ok(())
     }
 
+    /// Stake a compressed bear by moving its leaf to the global authority.
+    /// The merkle proof nodes follow the accounts
+    #[allow(clippy::too_many_arguments)]
     #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
-    pub fn claim_reward(ctx: Context<ClaimReward>, _global_bump: u8) -> Result<()> {
+    pub fn stake_cnft<'info>(
+        ctx: Context<'_, '_, '_, 'info, StakeCnft<'info>>,
+        _global_bump: u8,
+        asset_id: Pubkey,
+        root: [u8; 32],
+        metadata: MetadataArgs,
+        nonce: u64,
+        index: u32,
+        rarity_rate: u64,
+        rarity_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
         let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        require!(
+            (user_pool.staked_count as usize) < STAKE_MAX_COUNT,
+            StakingError::UserPoolFull
+        );
+
+        require!(
+            asset_id == mpl_bubblegum::utils::get_asset_id(&ctx.accounts.merkle_tree.key(), nonce),
+            StakingError::InvalidAssetId
+        );
+        collect_fee(
+            ctx.accounts.owner.to_account_info(),
+            ctx.accounts.sol_vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            global_authority.stake_fee,
+        )?;
+
+        // The leaf is only checked against these hashes by the transfer below,
+        // so the creators and name read here are the ones stored in the tree
+        let collection = BEAR_COLLECTION_ADDRESS.parse::<Pubkey>().unwrap();
+        require!(
+            metadata
+                .creators
+                .iter()
+                .any(|creator| creator.address == collection && creator.verified),
+            StakingError::UnkownOrNotAllowedNFTCollection
+        );
+        let id = parse_nft_id(&metadata.name)?;
+        msg!("NFT ID: {}", id);
+        let reward_rate = resolve_reward_rate(
+            &global_authority.rarity_root,
+            &asset_id,
+            id,
+            rarity_rate,
+            &rarity_proof,
+        )?;
+        let reward_rate = upgraded_reward_rate(&ctx.accounts.bear_upgrade, reward_rate)?;
+        msg!("Reward Rate: {}", reward_rate);
+
+        let cpi_accounts = mpl_bubblegum::cpi::accounts::Transfer {
+            tree_authority: ctx.accounts.tree_authority.to_account_info(),
+            leaf_owner: ctx.accounts.owner.to_account_info(),
+            leaf_delegate: ctx.accounts.leaf_delegate.to_account_info(),
+            new_leaf_owner: global_authority.to_account_info(),
+            merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+            log_wrapper: ctx.accounts.log_wrapper.to_account_info(),
+            compression_program: ctx.accounts.compression_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        mpl_bubblegum::cpi::transfer(
+            CpiContext::new(
+                ctx.accounts.bubblegum_program.to_account_info(),
+                cpi_accounts,
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+            root,
+            mpl_bubblegum::utils::hash_metadata(&metadata)?,
+            mpl_bubblegum::utils::hash_creators(&metadata.creators)?,
+            nonce,
+            index,
+        )?;
+
         let timestamp = Clock::get()?.unix_timestamp;
+        user_pool.add_nft(asset_id, id, Pubkey::default(), 0, reward_rate, timestamp);
+        global_authority.total_staked_count += 1;
+        let staked = user_pool.staked_nfts[user_pool.staked_count as usize - 1];
+        ctx.accounts
+            .stake_record
+            .record(ctx.accounts.owner.key(), 0, &staked);
+
+        let bonus_bps = set_bonus_bps(&ctx.accounts.set_bonus_config, &user_pool)?;
+        user_pool.update_set_bonus(bonus_bps, timestamp, &global_authority.emission)?;
+
+        Ok(())
+    }
+
+    /// Hand a staked compressed bear back to its owner, the accrued reward stays pending.
+    /// The hashes are those of the leaf as staked, the merkle proof nodes follow the accounts
+    #[allow(clippy::too_many_arguments)]
+    #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
+    pub fn unstake_cnft<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnstakeCnft<'info>>,
+        _global_bump: u8,
+        asset_id: Pubkey,
+        root: [u8; 32],
+        data_hash: [u8; 32],
+        creator_hash: [u8; 32],
+        nonce: u64,
+        index: u32,
+    ) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+
         require!(
-            timestamp - user_pool.last_claimed_time >= ONE_DAY,
-            StakingError::InvalidClaimRequest
+            asset_id == mpl_bubblegum::utils::get_asset_id(&ctx.accounts.merkle_tree.key(), nonce),
+            StakingError::InvalidAssetId
         );
-        let mut total_reward: u64 = 0;
+        collect_fee(
+            ctx.accounts.owner.to_account_info(),
+            ctx.accounts.sol_vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            global_authority.unstake_fee,
+        )?;
 
-        for i in 0..user_pool.staked_count {
-            let index = i as usize;
-            let mut last_claimed_time = user_pool.last_claimed_time;
-            if last_claimed_time < user_pool.staked_nfts[index].staked_time {
-                last_claimed_time = user_pool.staked_nfts[index].staked_time;
-            }
+        let timestamp = Clock::get()?.unix_timestamp;
+        user_pool.remove_nft(
+            asset_id,
+            timestamp,
+            &global_authority.emission,
+            &global_authority.reward_tokens,
+        )?;
+        global_authority.total_staked_count -= 1;
 
-            let reward: u64;
-            let mut reward_rate: u64;
-
-            match user_pool.staked_nfts[index].bear_id {
-                1..=1920 | 8001..=8480 => {
-                    reward_rate = 10;
-                }
-                1921..=3520 | 8481..=8880 => {
-                    reward_rate = 14;
-                }
-                3521..=4880 | 8881..=9220 => {
-                    reward_rate = 17;
-                }
-                4881..=6000 | 9221..=9500 => {
-                    reward_rate = 20;
-                }
-                6001..=6880 | 9501..=9720 => {
-                    reward_rate = 22;
-                }
-                6881..=7520 | 9721..=9880 => {
-                    reward_rate = 24;
-                }
-                7521..=7920 | 9881..=9980 => {
-                    reward_rate = 26;
-                }
-                7921..=7992 | 9981..=9998 => {
-                    reward_rate = 30;
-                }
-                7993..=8000 | 9999..=10000 => {
-                    reward_rate = 100;
-                }
-                _ => {
-                    return Err(StakingError::NotAllowedNFTID.into());
-                }
-            }
+        let seeds = &[GLOBAL_AUTHORITY_SEED.as_bytes(), &[_global_bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = mpl_bubblegum::cpi::accounts::Transfer {
+            tree_authority: ctx.accounts.tree_authority.to_account_info(),
+            leaf_owner: global_authority.to_account_info(),
+            leaf_delegate: global_authority.to_account_info(),
+            new_leaf_owner: ctx.accounts.owner.to_account_info(),
+            merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+            log_wrapper: ctx.accounts.log_wrapper.to_account_info(),
+            compression_program: ctx.accounts.compression_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        mpl_bubblegum::cpi::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.bubblegum_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+            root,
+            data_hash,
+            creator_hash,
+            nonce,
+            index,
+        )?;
 
-            match user_pool.staked_nfts[index].box_id {
-                1..=5000 => {
-                    reward_rate *= 1_100_000_000;
-                }
-                5001..=8000 => {
-                    reward_rate *= 1_200_000_000;
-                }
-                8001..=9500 => {
-                    reward_rate *= 1_300_000_000;
-                }
-                9501..=10000 => {
-                    reward_rate *= 1_500_000_000;
-                }
-                _ => {
-                    reward_rate *= 1_000_000_000;
-                }
-            }
+        close_stake_record(
+            &ctx.accounts.stake_record,
+            &ctx.accounts.owner.to_account_info(),
+        )?;
 
-            reward = (((timestamp - last_claimed_time) / ONE_DAY) as u64) * reward_rate;
-            total_reward += reward;
-        }
-        total_reward += user_pool.pending_reward;
-        user_pool.last_claimed_time = timestamp;
-        user_pool.pending_reward = 0;
+        let bonus_bps = set_bonus_bps(&ctx.accounts.set_bonus_config, &user_pool)?;
+        user_pool.update_set_bonus(bonus_bps, timestamp, &global_authority.emission)?;
+        record_points(
+            &ctx.accounts.leaderboard,
+            ctx.accounts.user_pool.key(),
+            &user_pool,
+        )?;
+
+        Ok(())
+    }
 
+    #[access_control(claimer(&ctx.accounts.user_pool, &ctx.accounts.claimer))]
+    pub fn claim_reward<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimReward<'info>>,
+        _global_bump: u8,
+        allow_partial: bool,
+        instant: bool,
+        reward_token_slots: Vec<u8>,
+    ) -> Result<()> {
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        collect_fee(
+            ctx.accounts.claimer.to_account_info(),
+            ctx.accounts.sol_vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.global_authority.claim_fee,
+        )?;
         require!(
-            ctx.accounts.reward_vault.amount >= total_reward,
-            StakingError::InsufficientRewardVault
+            ctx.accounts.user_reward_account.owner == user_pool.reward_destination(),
+            StakingError::InvalidRewardDestination
+        );
+
+        // Rewards parked by unstaking can be claimed any time, accruals only once a day
+        let accrual_due = timestamp - user_pool.last_claimed_time >= ONE_DAY;
+        let mut total_reward =
+            user_pool.claim_reward(timestamp, &ctx.accounts.global_authority.emission)?;
+        require!(
+            accrual_due || total_reward > 0 || !reward_token_slots.is_empty(),
+            StakingError::InvalidClaimRequest
         );
+        record_points(
+            &ctx.accounts.leaderboard,
+            ctx.accounts.user_pool.key(),
+            &user_pool,
+        )?;
+
+        // Pay out what the vault holds and carry the rest when partial claims are allowed
+        let vault_amount = ctx.accounts.reward_vault.amount;
+        if vault_amount < total_reward {
+            require!(allow_partial, StakingError::InsufficientRewardVault);
+
+            let shortfall = total_reward - vault_amount;
+            user_pool.pending_reward = shortfall;
+            total_reward = vault_amount;
+
+            emit!(RewardShortfall {
+                owner: user_pool.owner,
+                mint: ctx.accounts.reward_vault.mint,
+                claimed: total_reward,
+                shortfall,
+                timestamp,
+            });
+        }
 
         let seeds = &[GLOBAL_AUTHORITY_SEED.as_bytes(), &[_global_bump]];
         let signer = &[&seeds[..]];
         let token_program = ctx.accounts.token_program.to_account_info();
+
+        // Pay the selected co-sponsored tokens, passed as (vault, user token account) pairs
+        require!(
+            ctx.remaining_accounts.len() == reward_token_slots.len() * 2,
+            StakingError::InvalidRewardToken
+        );
+        for (i, slot) in reward_token_slots.iter().enumerate() {
+            let slot = *slot as usize;
+            let config = *ctx
+                .accounts
+                .global_authority
+                .reward_tokens
+                .get(slot)
+                .filter(|t| t.mint != Pubkey::default())
+                .ok_or(StakingError::InvalidRewardToken)?;
+
+            let vault = Account::<TokenAccount>::try_from(&ctx.remaining_accounts[i * 2])?;
+            let user_token_account =
+                Account::<TokenAccount>::try_from(&ctx.remaining_accounts[i * 2 + 1])?;
+            require!(
+                vault.mint == config.mint && vault.owner == ctx.accounts.global_authority.key(),
+                StakingError::InvalidRewardToken
+            );
+            require!(
+                user_token_account.mint == config.mint
+                    && user_token_account.owner == user_pool.reward_destination(),
+                StakingError::InvalidRewardDestination
+            );
+
+            let reward = user_pool.claim_extra_reward(slot, &config, timestamp)?;
+            let claimed = reward.min(vault.amount);
+            if claimed < reward {
+                user_pool.extra_pending_reward[slot] = reward - claimed;
+                emit!(RewardShortfall {
+                    owner: user_pool.owner,
+                    mint: config.mint,
+                    claimed,
+                    shortfall: reward - claimed,
+                    timestamp,
+                });
+            }
+
+            let cpi_accounts = Transfer {
+                from: vault.to_account_info(),
+                to: user_token_account.to_account_info(),
+                authority: ctx.accounts.global_authority.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
+                claimed,
+            )?;
+            emit!(RewardClaimed {
+                owner: user_pool.owner,
+                mint: config.mint,
+                amount: claimed,
+                vested: false,
+                timestamp,
+            });
+        }
+
+        // Stream the claim through the vesting schedule, or pay it now minus the haircut
+        let vesting_duration = ctx.accounts.global_authority.vesting_duration;
+        if vesting_duration > 0 {
+            if !instant {
+                require!(
+                    !ctx.accounts.vesting.data_is_empty(),
+                    StakingError::MissingVestingSchedule
+                );
+                let mut vesting = Account::<VestingSchedule>::try_from(&ctx.accounts.vesting)?;
+                require!(
+                    vesting.owner == user_pool.owner,
+                    StakingError::MissingVestingSchedule
+                );
+                vesting.add(total_reward, timestamp, vesting_duration);
+                vesting.exit(&crate::ID)?;
+                emit!(RewardClaimed {
+                    owner: user_pool.owner,
+                    mint: ctx.accounts.reward_vault.mint,
+                    amount: total_reward,
+                    vested: true,
+                    timestamp,
+                });
+                return Ok(());
+            }
+
+            let haircut = (total_reward as u128
+                * ctx.accounts.global_authority.instant_haircut_bps as u128
+                / BPS_DENOMINATOR) as u64;
+            total_reward -= haircut;
+        }
+
         let cpi_accounts = Transfer {
             from: ctx.accounts.reward_vault.to_account_info(),
             to: ctx.accounts.user_reward_account.to_account_info(),
@@ -299,112 +967,2023 @@ pub mod armory_staking {
             CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
             total_reward,
         )?;
+        emit!(RewardClaimed {
+            owner: user_pool.owner,
+            mint: ctx.accounts.reward_vault.mint,
+            amount: total_reward,
+            vested: false,
+            timestamp,
+        });
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(bump: u8)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
+    #[access_control(current_version(&ctx.accounts.user_pool))]
+    pub fn view_streak(ctx: Context<ViewUserPool>, bear_mint: Pubkey) -> Result<StreakView> {
+        let user_pool = ctx.accounts.user_pool.load()?;
+        let index = user_pool
+            .find_nft(&bear_mint)
+            .ok_or(StakingError::InvalidNftAddress)?;
 
-    #[account(init, seeds = [GLOBAL_AUTHORITY_SEED.as_ref()], bump, space = 48, payer = admin)]
-    pub global_authority: Account<'info, GlobalPool>,
+        Ok(user_pool.staked_nfts[index].streak(Clock::get()?.unix_timestamp))
+    }
 
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    pub fn create_voter_weight_record(ctx: Context<CreateVoterWeightRecord>) -> Result<()> {
+        let governance_config = &ctx.accounts.governance_config;
+        let voter_weight_record = &mut ctx.accounts.voter_weight_record;
+        voter_weight_record.realm = governance_config.realm;
+        voter_weight_record.governing_token_mint = governance_config.governing_token_mint;
+        voter_weight_record.governing_token_owner = ctx.accounts.owner.key();
+        voter_weight_record.voter_weight_expiry = Some(0);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitUserPool<'info> {
-    #[account(zero)]
-    pub user_pool: AccountLoader<'info, UserPool>,
+    /// Record the voting power of the signer's staked bears at the current slot, SPL Governance
+    /// only accepts a snapshot taken in the slot it is used in. The stake records of the bears
+    /// are passed as remaining accounts sorted by address, and every counted bear stays locked
+    /// in its pool for `vote_lock` so it cannot be counted again under another wallet.
+    pub fn snapshot<'info>(ctx: Context<'_, '_, '_, 'info, Snapshot<'info>>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let governance_config = &ctx.accounts.governance_config;
+        let voter_weight_record = &mut ctx.accounts.voter_weight_record;
+        require!(
+            voter_weight_record.governing_token_owner == owner,
+            StakingError::InvalidVoter
+        );
 
+        let clock = Clock::get()?;
+        let lock_until = clock
+            .unix_timestamp
+            .checked_add(governance_config.vote_lock)
+            .ok_or(StakingError::MathOverflow)?;
+        let mut voter_weight: u64 = 0;
+        let mut previous = Pubkey::default();
+        for info in ctx.remaining_accounts.iter() {
+            require!(previous < info.key(), StakingError::InvalidStakeRecords);
+            previous = info.key();
+
+            let mut record = Account::<StakeRecord>::try_from(info)?;
+            require!(record.owner == owner, StakingError::InvalidStakeRecords);
+            let power = record.voting_power(clock.unix_timestamp, governance_config)?;
+            voter_weight = voter_weight
+                .checked_add(power)
+                .ok_or(StakingError::MathOverflow)?;
+            record.vote_lock_until = record.vote_lock_until.max(lock_until);
+            record.exit(&crate::ID)?;
+        }
+
+        voter_weight_record.voter_weight = voter_weight;
+        voter_weight_record.voter_weight_expiry = Some(clock.slot);
+        voter_weight_record.weight_action = None;
+        voter_weight_record.weight_action_target = None;
+
+        emit!(VotingPowerSnapshot {
+            owner,
+            voter_weight,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Whether `owner` stakes `nft_mint` in any pool, for other programs to call through CPI
+    pub fn is_staked(ctx: Context<IsStaked>, owner: Pubkey) -> Result<bool> {
+        let record = StakeRecord::load(&ctx.accounts.stake_record, &ctx.accounts.nft_mint.key());
+        Ok(matches!(record, Some(record) if record.owner == owner))
+    }
+
+    /// Create the stake record of a bear staked before stake records existed, from its entry in
+    /// the user pool. Anyone can pay for it.
+    #[access_control(current_version(&ctx.accounts.user_pool))]
+    pub fn backfill_stake_record(ctx: Context<BackfillStakeRecord>) -> Result<()> {
+        let user_pool = ctx.accounts.user_pool.load()?;
+        let index = user_pool
+            .find_nft(&ctx.accounts.nft_mint.key())
+            .ok_or(StakingError::InvalidNftAddress)?;
+        let staked = user_pool.staked_nfts[index];
+        ctx.accounts
+            .stake_record
+            .record(user_pool.owner, user_pool.pool_id, &staked);
+
+        Ok(())
+    }
+
+    #[access_control(claimer(&ctx.accounts.user_pool, &ctx.accounts.claimer))]
+    pub fn withdraw_vested(ctx: Context<WithdrawVested>, _global_bump: u8) -> Result<()> {
+        let user_pool = ctx.accounts.user_pool.load()?;
+        let vesting = &mut ctx.accounts.vesting;
+        require!(
+            vesting.owner == user_pool.owner,
+            StakingError::MissingVestingSchedule
+        );
+        require!(
+            ctx.accounts.user_reward_account.owner == user_pool.reward_destination(),
+            StakingError::InvalidRewardDestination
+        );
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        vesting.settle(timestamp);
+        let amount = vesting.claimable_amount;
+        require!(amount > 0, StakingError::NothingToWithdraw);
+        require!(
+            ctx.accounts.reward_vault.amount >= amount,
+            StakingError::InsufficientRewardVault
+        );
+        vesting.claimable_amount = 0;
+
+        let seeds = &[GLOBAL_AUTHORITY_SEED.as_bytes(), &[_global_bump]];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.user_reward_account.to_account_info(),
+            authority: ctx.accounts.global_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    /// Move a staked bear out of the user pool into a receipt NFT minted to the owner.
+    /// Rewards accrued so far stay pending in the user pool, later ones follow the receipt.
+    /// A receipt only accrues MEDAL: co-sponsored reward tokens, points and set bonuses stop
+    /// for the bear until it is staked again.
+    #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
+    pub fn mint_receipt(ctx: Context<MintReceipt>, _global_bump: u8) -> Result<()> {
+        let global_authority = &ctx.accounts.global_authority;
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        let bear_mint = ctx.accounts.nft_mint.key();
+        let index = user_pool
+            .find_nft(&bear_mint)
+            .ok_or(StakingError::InvalidNftAddress)?;
+        let staked = user_pool.staked_nfts[index];
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        let (metadata, _) = Pubkey::find_program_address(
+            &[
+                mpl_token_metadata::state::PREFIX.as_bytes(),
+                mpl_token_metadata::id().as_ref(),
+                bear_mint.as_ref(),
+            ],
+            &mpl_token_metadata::id(),
+        );
+        require!(
+            metadata == ctx.accounts.mint_metadata.key(),
+            StakingError::InvalidMetadata
+        );
+        let bear_metadata = Metadata::from_account_info(&ctx.accounts.mint_metadata)?;
+
+        user_pool.remove_nft(
+            bear_mint,
+            timestamp,
+            &global_authority.emission,
+            &global_authority.reward_tokens,
+        )?;
+        let bonus_bps = set_bonus_bps(&ctx.accounts.set_bonus_config, &user_pool)?;
+        user_pool.update_set_bonus(bonus_bps, timestamp, &global_authority.emission)?;
+        record_points(
+            &ctx.accounts.leaderboard,
+            ctx.accounts.user_pool.key(),
+            &user_pool,
+        )?;
+
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.bear_mint = staked.bear_mint;
+        receipt.bear_id = staked.bear_id;
+        receipt.box_mint = staked.box_mint;
+        receipt.box_id = staked.box_id;
+        receipt.staked_time = staked.staked_time;
+        receipt.reward_rate = staked.reward_rate;
+        receipt.last_claimed_time = timestamp;
+        move_stake_record(&ctx.accounts.stake_record, &bear_mint, receipt.key())?;
+
+        let seeds = &[GLOBAL_AUTHORITY_SEED.as_bytes(), &[_global_bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            to: ctx.accounts.receipt_account.to_account_info(),
+            authority: global_authority.to_account_info(),
+        };
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            1,
+        )?;
+
+        invoke_signed(
+            &mpl_token_metadata::instruction::create_metadata_accounts_v3(
+                mpl_token_metadata::id(),
+                ctx.accounts.receipt_metadata.key(),
+                ctx.accounts.receipt_mint.key(),
+                global_authority.key(),
+                ctx.accounts.owner.key(),
+                global_authority.key(),
+                format!("Staked Bear #{}", receipt.bear_id),
+                RECEIPT_SYMBOL.to_string(),
+                bear_metadata
+                    .data
+                    .uri
+                    .trim_matches(char::from(0))
+                    .to_string(),
+                None,
+                0,
+                true,
+                true,
+                None,
+                None,
+                None,
+            ),
+            &[
+                ctx.accounts.receipt_metadata.to_account_info(),
+                ctx.accounts.receipt_mint.to_account_info(),
+                global_authority.to_account_info(),
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            signer,
+        )?;
+
+        emit!(ReceiptMinted {
+            owner: user_pool.owner,
+            bear_mint,
+            receipt_mint: ctx.accounts.receipt_mint.key(),
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Burn a receipt to unstake its bear and box, paying the MEDAL it accrued to the holder.
+    /// Nothing else accrues on a receipt, see `mint_receipt`.
+    pub fn unstake_receipt(ctx: Context<UnstakeReceipt>, _global_bump: u8) -> Result<()> {
+        let global_authority = &mut ctx.accounts.global_authority;
+        let receipt = &ctx.accounts.receipt;
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        collect_fee(
+            ctx.accounts.holder.to_account_info(),
+            ctx.accounts.sol_vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            global_authority.unstake_fee,
+        )?;
+
+        // Redeeming skips vesting, so it takes the instant claim haircut
+        let mut reward = receipt.accrued_reward(timestamp, &global_authority.emission)?;
+        if global_authority.vesting_duration > 0 {
+            reward -= (reward as u128 * global_authority.instant_haircut_bps as u128
+                / BPS_DENOMINATOR) as u64;
+        }
+        require!(
+            ctx.accounts.reward_vault.amount >= reward,
+            StakingError::InsufficientRewardVault
+        );
+
+        let seeds = &[GLOBAL_AUTHORITY_SEED.as_bytes(), &[_global_bump]];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.dest_bear_account.to_account_info(),
+            to: ctx.accounts.user_bear_account.to_account_info(),
+            authority: global_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
+            1,
+        )?;
+
+        if receipt.box_id != 0 {
+            let dest_box_account =
+                Account::<TokenAccount>::try_from(&ctx.accounts.dest_box_account)?;
+            require!(
+                dest_box_account.mint == receipt.box_mint
+                    && dest_box_account.owner == global_authority.key()
+                    && dest_box_account.amount == 1,
+                StakingError::InvalidReceipt
+            );
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.dest_box_account.to_account_info(),
+                to: ctx.accounts.user_box_account.to_account_info(),
+                authority: global_authority.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
+                1,
+            )?;
+        }
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.user_reward_account.to_account_info(),
+            authority: global_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
+            reward,
+        )?;
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            from: ctx.accounts.receipt_account.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        };
+        token::burn(CpiContext::new(token_program, cpi_accounts), 1)?;
+        global_authority.total_staked_count -= 1;
+        close_stake_record(
+            &ctx.accounts.stake_record,
+            &ctx.accounts.holder.to_account_info(),
+        )?;
+
+        emit!(RewardClaimed {
+            owner: ctx.accounts.holder.key(),
+            mint: ctx.accounts.reward_vault.mint,
+            amount: reward,
+            vested: false,
+            timestamp,
+        });
+        emit!(ReceiptRedeemed {
+            holder: ctx.accounts.holder.key(),
+            bear_mint: receipt.bear_mint,
+            receipt_mint: ctx.accounts.receipt_mint.key(),
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Burn a box to raise the bear held by the signer one tier for all its future stakes. The
+    /// rarity rate and proof are the ones `stake_nft` takes, so the cap is checked on the rate the
+    /// bear stakes at. A box with a verified collection has its collection metadata follow the
+    /// accounts.
+    pub fn upgrade_bear<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpgradeBear<'info>>,
+        rarity_rate: u64,
+        rarity_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let bear_mint = ctx.accounts.bear_mint.key();
+        let box_mint = ctx.accounts.box_mint.key();
+        let bear_id = verify_nft(
+            &ctx.accounts.bear_metadata,
+            &bear_mint,
+            &BEAR_COLLECTION_ADDRESS.parse::<Pubkey>().unwrap(),
+        )?;
+        let box_id = verify_nft(
+            &ctx.accounts.box_metadata,
+            &box_mint,
+            &BOX_COLLECTION_ADDRESS.parse::<Pubkey>().unwrap(),
+        )?;
+
+        let bear_upgrade = &mut ctx.accounts.bear_upgrade;
+        let rate = resolve_reward_rate(
+            &ctx.accounts.global_authority.rarity_root,
+            &bear_mint,
+            bear_id,
+            rarity_rate,
+            &rarity_proof,
+        )?;
+        require!(
+            upgraded_rate(rate, bear_upgrade.tiers + 1) > upgraded_rate(rate, bear_upgrade.tiers),
+            StakingError::UpgradeMaxed
+        );
+
+        let mut accounts = vec![
+            ctx.accounts.box_metadata.to_account_info(),
+            ctx.accounts.owner.to_account_info(),
+            ctx.accounts.box_mint.to_account_info(),
+            ctx.accounts.user_box_account.to_account_info(),
+            ctx.accounts.box_edition.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+        ];
+        let box_collection_metadata = ctx.remaining_accounts.first();
+        if let Some(box_collection_metadata) = box_collection_metadata {
+            accounts.push(box_collection_metadata.clone());
+        }
+        invoke(
+            &mpl_token_metadata::instruction::burn_nft(
+                mpl_token_metadata::id(),
+                ctx.accounts.box_metadata.key(),
+                ctx.accounts.owner.key(),
+                box_mint,
+                ctx.accounts.user_box_account.key(),
+                ctx.accounts.box_edition.key(),
+                ctx.accounts.token_program.key(),
+                box_collection_metadata.map(|metadata| metadata.key()),
+            ),
+            &accounts,
+        )?;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        bear_upgrade.bear_mint = bear_mint;
+        bear_upgrade.tiers += 1;
+        bear_upgrade.last_upgrade_time = timestamp;
+
+        emit!(BearUpgraded {
+            owner: ctx.accounts.owner.key(),
+            bear_mint,
+            box_mint,
+            box_id,
+            tiers: bear_upgrade.tiers,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Offer the reward stream of a bear staked in the main pool for `duration` seconds at `price`
+    #[access_control(user(&ctx.accounts.user_pool, &ctx.accounts.owner))]
+    pub fn list_for_rent(
+        ctx: Context<ListForRent>,
+        price: u64,
+        duration: i64,
+        pay_in_medal: bool,
+    ) -> Result<()> {
+        let user_pool = ctx.accounts.user_pool.load()?;
+        require!(
+            duration > 0 && duration <= MAX_RENTAL_DURATION,
+            StakingError::InvalidRentalDuration
+        );
+        user_pool
+            .find_nft(&ctx.accounts.nft_mint.key())
+            .ok_or(StakingError::InvalidNftAddress)?;
+
+        let listing = &mut ctx.accounts.listing;
+        listing.owner = ctx.accounts.owner.key();
+        listing.nft_mint = ctx.accounts.nft_mint.key();
+        listing.price = price;
+        listing.duration = duration;
+        listing.pay_in_medal = pay_in_medal;
+        Ok(())
+    }
+
+    /// Close a listing once its last rental expired and the renter was paid
+    pub fn cancel_rental_listing(ctx: Context<CancelRentalListing>) -> Result<()> {
+        require!(ctx.accounts.listing.is_settled(), StakingError::BearRented);
+        Ok(())
+    }
+
+    /// Pay the owner to receive the rewards of a listed bear until the rental expires. A listing
+    /// priced in MEDAL has the renter's and the owner's MEDAL accounts follow the accounts.
+    #[access_control(current_version(&ctx.accounts.user_pool))]
+    pub fn rent_nft<'info>(ctx: Context<'_, '_, '_, 'info, RentNft<'info>>) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            user_pool.owner == listing.owner && user_pool.pool_id == 0 && listing.is_settled(),
+            StakingError::RentalUnavailable
+        );
+        let index = user_pool
+            .find_nft(&listing.nft_mint)
+            .ok_or(StakingError::RentalUnavailable)?;
+
+        if listing.pay_in_medal {
+            require!(
+                ctx.remaining_accounts.len() == 2,
+                StakingError::InvalidRentalPayment
+            );
+            let renter_medal_account =
+                Account::<TokenAccount>::try_from(&ctx.remaining_accounts[0])?;
+            let owner_medal_account =
+                Account::<TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
+            let medal_mint = MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap();
+            require!(
+                renter_medal_account.mint == medal_mint
+                    && renter_medal_account.owner == ctx.accounts.renter.key()
+                    && owner_medal_account.mint == medal_mint
+                    && owner_medal_account.owner == listing.owner,
+                StakingError::InvalidRentalPayment
+            );
+            let cpi_accounts = Transfer {
+                from: renter_medal_account.to_account_info(),
+                to: owner_medal_account.to_account_info(),
+                authority: ctx.accounts.renter.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+                listing.price,
+            )?;
+        } else {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.renter.to_account_info(),
+                        to: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                listing.price,
+            )?;
+        }
+
+        // The owner keeps what accrued so far, the window is cut out of its stream from now on
+        user_pool.settle_reward(timestamp, &ctx.accounts.global_authority.emission)?;
+        let rent_end = timestamp
+            .checked_add(listing.duration)
+            .ok_or(StakingError::MathOverflow)?;
+        user_pool.staked_nfts[index].rent_start = timestamp;
+        user_pool.staked_nfts[index].rent_end = rent_end;
+
+        let staked = user_pool.staked_nfts[index];
+        listing.renter = ctx.accounts.renter.key();
+        listing.rent_start = timestamp;
+        listing.rent_end = rent_end;
+        listing.claimed_until = timestamp;
+        listing.staked_time = staked.staked_time;
+        listing.reward_rate = staked_reward_rate(&staked)?;
+
+        emit!(NftRented {
+            owner: listing.owner,
+            renter: listing.renter,
+            bear_mint: listing.nft_mint,
+            price: listing.price,
+            pay_in_medal: listing.pay_in_medal,
+            rent_end,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a renter what the bear accrued during the rental so far. Anyone can crank it,
+    /// the reward only goes to the renter.
+    pub fn claim_rental_reward(ctx: Context<ClaimRentalReward>, _global_bump: u8) -> Result<()> {
+        let global_authority = &ctx.accounts.global_authority;
+        let listing = &mut ctx.accounts.listing;
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        // Rentals skip vesting, so they take the instant claim haircut
+        let mut reward = listing.accrued_reward(timestamp, &global_authority.emission)?;
+        if global_authority.vesting_duration > 0 {
+            reward -= (reward as u128 * global_authority.instant_haircut_bps as u128
+                / BPS_DENOMINATOR) as u64;
+        }
+        require!(
+            ctx.accounts.reward_vault.amount >= reward,
+            StakingError::InsufficientRewardVault
+        );
+        listing.claimed_until = timestamp.min(listing.rent_end);
+
+        let seeds = &[GLOBAL_AUTHORITY_SEED.as_bytes(), &[_global_bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.renter_reward_account.to_account_info(),
+            authority: global_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            reward,
+        )?;
+
+        emit!(RewardClaimed {
+            owner: listing.renter,
+            mint: ctx.accounts.reward_vault.mint,
+            amount: reward,
+            vested: false,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        pool_id: u64,
+        pool_admin: Pubkey,
+        bear_collection: Pubkey,
+        box_collection: Pubkey,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        require!(
+            pool_id != 0 && start_time < end_time,
+            StakingError::InvalidPoolConfig
+        );
+        let pool = &mut ctx.accounts.pool;
+        pool.pool_id = pool_id;
+        pool.admin = pool_admin;
+        pool.bear_collection = bear_collection;
+        pool.box_collection = box_collection;
+        pool.reward_mint = ctx.accounts.reward_mint.key();
+        pool.start_time = start_time;
+        pool.end_time = end_time;
+
+        Ok(())
+    }
+
+    #[access_control(pool_admin(&ctx.accounts.pool, &ctx.accounts.admin))]
+    pub fn update_pool(
+        ctx: Context<UpdatePool>,
+        rarity_root: [u8; 32],
+        emission: EmissionSchedule,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        require!(
+            start_time < end_time
+                && emission.epoch_length >= 0
+                && emission.decay_bps as u128 <= BPS_DENOMINATOR,
+            StakingError::InvalidPoolConfig
+        );
+        let pool = &mut ctx.accounts.pool;
+        require!(
+            pool.emission
+                .can_replace(&emission, Clock::get()?.unix_timestamp),
+            StakingError::EmissionScheduleStarted
+        );
+        pool.rarity_root = rarity_root;
+        pool.emission = emission;
+        pool.start_time = start_time;
+        pool.end_time = end_time;
+
+        Ok(())
+    }
+
+    #[access_control(admin(&ctx.accounts.global_authority, &ctx.accounts.admin))]
+    pub fn create_leaderboard(ctx: Context<CreateLeaderboard>, pool_id: u64) -> Result<()> {
+        ctx.accounts.leaderboard.pool_id = pool_id;
+
+        Ok(())
+    }
+
+    pub fn init_pool_user(ctx: Context<InitPoolUser>) -> Result<()> {
+        let mut user_pool = ctx.accounts.user_pool.load_init()?;
+        user_pool.owner = ctx.accounts.owner.key();
+        user_pool.version = USER_POOL_VERSION;
+        user_pool.pool_id = ctx.accounts.pool.pool_id;
+        msg!("Owner: {:?}", user_pool.owner.to_string());
+
+        Ok(())
+    }
+
+    /// Stake a bear in a seasonal pool. A box staked along follows the accounts as its metadata,
+    /// the owner's token account and the pool's escrow token account.
+    #[access_control(pool_user(&ctx.accounts.user_pool, &ctx.accounts.owner, &ctx.accounts.pool))]
+    pub fn stake_pool_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, StakePoolNft<'info>>,
+        rarity_rate: u64,
+        rarity_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            timestamp >= pool.start_time && timestamp < pool.end_time,
+            StakingError::PoolNotActive
+        );
+        require!(
+            (user_pool.staked_count as usize) < STAKE_MAX_COUNT,
+            StakingError::UserPoolFull
+        );
+
+        let id = verify_nft(
+            &ctx.accounts.mint_metadata,
+            &ctx.accounts.nft_mint.key(),
+            &pool.bear_collection,
+        )?;
+        let reward_rate = resolve_reward_rate(
+            &pool.rarity_root,
+            &ctx.accounts.nft_mint.key(),
+            id,
+            rarity_rate,
+            &rarity_proof,
+        )?;
+        let reward_rate = upgraded_reward_rate(&ctx.accounts.bear_upgrade, reward_rate)?;
+
+        let token_program = &ctx.accounts.token_program;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_bear_account.to_account_info(),
+            to: ctx.accounts.dest_bear_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(token_program.to_account_info(), cpi_accounts),
+            1,
+        )?;
+
+        // The box id is read from its verified metadata instead of trusting the caller
+        let mut box_id = 0;
+        require!(
+            matches!(ctx.remaining_accounts.len(), 0 | 3),
+            StakingError::InvalidNftAddress
+        );
+        if let [box_metadata, user_box_account, dest_box_account] = ctx.remaining_accounts {
+            let box_mint = ctx.accounts.nft_box_mint.key();
+            box_id = verify_nft(box_metadata, &box_mint, &pool.box_collection)?;
+
+            let user_box = Account::<TokenAccount>::try_from(user_box_account)?;
+            let dest_box = Account::<TokenAccount>::try_from(dest_box_account)?;
+            require!(
+                user_box.mint == box_mint
+                    && user_box.owner == ctx.accounts.owner.key()
+                    && user_box.amount == 1
+                    && dest_box.mint == box_mint
+                    && dest_box.owner == pool.key(),
+                StakingError::InvalidNftAddress
+            );
+            let cpi_accounts = Transfer {
+                from: user_box_account.to_account_info(),
+                to: dest_box_account.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new(token_program.to_account_info(), cpi_accounts),
+                1,
+            )?;
+        }
+
+        user_pool.add_nft(
+            ctx.accounts.nft_mint.key(),
+            id,
+            ctx.accounts.nft_box_mint.key(),
+            box_id,
+            reward_rate,
+            timestamp,
+        );
+        pool.staked_count += 1;
+        ctx.accounts.global_authority.total_staked_count += 1;
+        let staked = user_pool.staked_nfts[user_pool.staked_count as usize - 1];
+        ctx.accounts
+            .stake_record
+            .record(ctx.accounts.owner.key(), pool.pool_id, &staked);
+
+        Ok(())
+    }
+
+    /// Unstake a bear from a seasonal pool. A box staked along needs the owner's token account
+    /// and the pool's escrow token account to follow the accounts.
+    #[access_control(pool_user(&ctx.accounts.user_pool, &ctx.accounts.owner, &ctx.accounts.pool))]
+    pub fn unstake_pool_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, UnstakePoolNft<'info>>,
+        _pool_bump: u8,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        let index = user_pool
+            .find_nft(&ctx.accounts.nft_mint.key())
+            .ok_or(StakingError::InvalidNftAddress)?;
+        let staked = user_pool.staked_nfts[index];
+
+        let pool_id = pool.pool_id.to_le_bytes();
+        let seeds = &[POOL_SEED.as_bytes(), pool_id.as_ref(), &[_pool_bump]];
+        let signer = &[&seeds[..]];
+        let token_program = &ctx.accounts.token_program;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.dest_bear_account.to_account_info(),
+            to: ctx.accounts.user_bear_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer),
+            1,
+        )?;
+
+        if staked.box_id != 0 {
+            // The pool escrows every staker's boxes, only the one staked with this bear leaves
+            let (user_box_account, dest_box_account) = match ctx.remaining_accounts {
+                [user_box_account, dest_box_account] => (user_box_account, dest_box_account),
+                _ => return Err(error!(StakingError::InvalidNftAddress)),
+            };
+            let user_box = Account::<TokenAccount>::try_from(user_box_account)?;
+            let dest_box = Account::<TokenAccount>::try_from(dest_box_account)?;
+            require!(
+                user_box.mint == staked.box_mint
+                    && user_box.owner == ctx.accounts.owner.key()
+                    && dest_box.mint == staked.box_mint
+                    && dest_box.owner == pool.key()
+                    && dest_box.amount == 1,
+                StakingError::InvalidNftAddress
+            );
+            let cpi_accounts = Transfer {
+                from: dest_box_account.to_account_info(),
+                to: user_box_account.to_account_info(),
+                authority: pool.to_account_info(),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer),
+                1,
+            )?;
+        }
+
+        // Rewards stop accruing when the season ends
+        let timestamp = Clock::get()?.unix_timestamp.min(pool.end_time);
+        user_pool.remove_nft(ctx.accounts.nft_mint.key(), timestamp, &pool.emission, &[])?;
+        pool.staked_count -= 1;
+        ctx.accounts.global_authority.total_staked_count -= 1;
+        close_stake_record(
+            &ctx.accounts.stake_record,
+            &ctx.accounts.owner.to_account_info(),
+        )?;
+        record_points(
+            &ctx.accounts.leaderboard,
+            ctx.accounts.user_pool.key(),
+            &user_pool,
+        )?;
+
+        Ok(())
+    }
+
+    #[access_control(pool_user(&ctx.accounts.user_pool, &ctx.accounts.owner, &ctx.accounts.pool))]
+    pub fn claim_pool_reward(ctx: Context<ClaimPoolReward>, _pool_bump: u8) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let mut user_pool = ctx.accounts.user_pool.load_mut()?;
+        let timestamp = Clock::get()?.unix_timestamp.min(pool.end_time);
+
+        let mut total_reward = user_pool.pending_reward;
+        total_reward += user_pool.total_accrued_reward(timestamp, &pool.emission)?;
+        user_pool.settle_points(timestamp)?;
+        if user_pool.last_claimed_time < timestamp {
+            user_pool.last_claimed_time = timestamp;
+        }
+        record_points(
+            &ctx.accounts.leaderboard,
+            ctx.accounts.user_pool.key(),
+            &user_pool,
+        )?;
+
+        // Pay out what the vault holds and carry the rest
+        let claimed = total_reward.min(ctx.accounts.reward_vault.amount);
+        user_pool.pending_reward = total_reward - claimed;
+        if claimed < total_reward {
+            emit!(RewardShortfall {
+                owner: user_pool.owner,
+                mint: pool.reward_mint,
+                claimed,
+                shortfall: total_reward - claimed,
+                timestamp,
+            });
+        }
+
+        let pool_id = pool.pool_id.to_le_bytes();
+        let seeds = &[POOL_SEED.as_bytes(), pool_id.as_ref(), &[_pool_bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_vault.to_account_info(),
+            to: ctx.accounts.user_reward_account.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            ),
+            claimed,
+        )?;
+        emit!(RewardClaimed {
+            owner: user_pool.owner,
+            mint: pool.reward_mint,
+            amount: claimed,
+            vested: false,
+            timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(init, seeds = [GLOBAL_AUTHORITY_SEED.as_ref()], bump, space = GlobalPool::SIZE, payer = admin)]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalPool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    /// CHECK: The old layout can't be loaded as GlobalPool, it is checked in the handler
+    pub global_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmissionSchedule<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+}
+
+#[derive(Accounts)]
+pub struct SetRarityRoot<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+}
+
+#[derive(Accounts)]
+pub struct SetVestingConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+}
+
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct WithdrawSolFees<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddRewardToken<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    pub reward_mint: Account<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardTokenRate<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+}
+
+#[derive(Accounts)]
+pub struct InitSetBonusConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        init,
+        seeds = [SET_BONUS_SEED.as_ref()],
+        bump,
+        space = 200,
+        payer = admin,
+    )]
+    pub set_bonus_config: Account<'info, SetBonusConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitGovernance<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        init,
+        seeds = [GOVERNANCE_SEED.as_ref()],
+        bump,
+        space = 96,
+        payer = admin,
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVotingWeights<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        mut,
+        seeds = [GOVERNANCE_SEED.as_ref()],
+        bump,
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetSetBonus<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        mut,
+        seeds = [SET_BONUS_SEED.as_ref()],
+        bump,
+    )]
+    pub set_bonus_config: Account<'info, SetBonusConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitUserPool<'info> {
+    #[account(zero)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserPool<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Old layouts can't be loaded as UserPool, ownership and discriminator are checked in the handler
+    #[account(mut)]
+    pub user_pool: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitVesting<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        seeds = [VESTING_SEED.as_ref(), owner.key().as_ref()],
+        bump,
+        space = 440,
+        payer = owner,
+    )]
+    pub vesting: Account<'info, VestingSchedule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimConfig<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct StakeNft<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub nft_mint: Account<'info, Mint>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub nft_box_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_bear_account.mint == *nft_mint.to_account_info().key,
+        constraint = user_bear_account.owner == *owner.key,
+        constraint = user_bear_account.amount == 1,
+    )]
+    pub user_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = dest_bear_account.mint == *nft_mint.to_account_info().key,
+        constraint = dest_bear_account.owner == *global_authority.to_account_info().key,
+    )]
+    pub dest_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub user_box_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub dest_box_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = reward_vault.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = reward_vault.owner == global_authority.key(),
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_reward_account.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = user_reward_account.owner == owner.key(),
+    )]
+    pub user_reward_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub mint_metadata: AccountInfo<'info>,
+
+    #[account(
+        init,
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+        space = StakeRecord::SIZE,
+        payer = owner,
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    #[account(
+        seeds = [UPGRADE_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a BearUpgrade once the bear was upgraded
+    pub bear_upgrade: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [SET_BONUS_SEED.as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a SetBonusConfig once the admin created it
+    pub set_bonus_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    #[account(constraint = token_metadata_program.key == &mpl_token_metadata::ID)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    bump: u8,
+)]
+pub struct UnstakeNft<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub nft_mint: Account<'info, Mint>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub nft_box_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = user_bear_account.mint == *nft_mint.to_account_info().key,
+        constraint = user_bear_account.owner == *owner.key,
+    )]
+    pub user_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = dest_bear_account.mint == *nft_mint.to_account_info().key,
+        constraint = dest_bear_account.owner == *global_authority.to_account_info().key,
+        constraint = dest_bear_account.amount == 1,
+    )]
+    pub dest_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub user_box_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub dest_box_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    /// CHECK: Ranked on once the admin created it, checked against the user pool's pool id
+    pub leaderboard: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: Closed when it exists, bears staked before stake records have none
+    pub stake_record: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [SET_BONUS_SEED.as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a SetBonusConfig once the admin created it
+    pub set_bonus_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8, asset_id: Pubkey)]
+pub struct StakeCnft<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    /// CHECK: Checked by the bubblegum program, the owner unless a delegate is set
+    pub leaf_delegate: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by the bubblegum program
+    pub tree_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked by the account compression program
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [STAKE_RECORD_SEED.as_ref(), asset_id.as_ref()],
+        bump,
+        space = StakeRecord::SIZE,
+        payer = owner,
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    #[account(
+        seeds = [SET_BONUS_SEED.as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a SetBonusConfig once the admin created it
+    pub set_bonus_config: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [UPGRADE_SEED.as_ref(), asset_id.as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a BearUpgrade once the bear was upgraded
+    pub bear_upgrade: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub bubblegum_program: Program<'info, Bubblegum>,
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub log_wrapper: Program<'info, Noop>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8, asset_id: Pubkey)]
+pub struct UnstakeCnft<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    /// CHECK: Checked by the bubblegum program
+    pub tree_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked by the account compression program
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Ranked on once the admin created it, checked against the user pool's pool id
+    pub leaderboard: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_RECORD_SEED.as_ref(), asset_id.as_ref()],
+        bump,
+    )]
+    /// CHECK: Closed when it exists
+    pub stake_record: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [SET_BONUS_SEED.as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a SetBonusConfig once the admin created it
+    pub set_bonus_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub bubblegum_program: Program<'info, Bubblegum>,
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub log_wrapper: Program<'info, Noop>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct ClaimReward<'info> {
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        mut,
+        constraint = reward_vault.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = reward_vault.owner == global_authority.key(),
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_reward_account.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+    )]
+    pub user_reward_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Loaded for a vested claim, which needs the owner's schedule from init_vesting
+    pub vesting: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Ranked on once the admin created it, checked against the user pool's pool id
+    pub leaderboard: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ViewUserPool<'info> {
+    pub user_pool: AccountLoader<'info, UserPool>,
+}
+
+#[derive(Accounts)]
+pub struct CreateVoterWeightRecord<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [GOVERNANCE_SEED.as_ref()],
+        bump,
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(
+        init,
+        seeds = [
+            VOTER_WEIGHT_SEED.as_ref(),
+            governance_config.realm.as_ref(),
+            governance_config.governing_token_mint.as_ref(),
+            owner.key().as_ref(),
+        ],
+        bump,
+        space = 164,
+        payer = owner,
+    )]
+    pub voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Snapshot<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [GOVERNANCE_SEED.as_ref()],
+        bump,
+    )]
+    pub governance_config: Account<'info, GovernanceConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            VOTER_WEIGHT_SEED.as_ref(),
+            governance_config.realm.as_ref(),
+            governance_config.governing_token_mint.as_ref(),
+            voter_weight_record.governing_token_owner.as_ref(),
+        ],
+        bump,
+    )]
+    pub voter_weight_record: Account<'info, VoterWeightRecord>,
+}
+
+#[derive(Accounts)]
+pub struct IsStaked<'info> {
+    /// CHECK: Only seeds the stake record
+    pub nft_mint: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: Empty when the bear is not staked
+    pub stake_record: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BackfillStakeRecord<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    /// CHECK: Only seeds the stake record, looked up in the user pool
+    pub nft_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+        space = StakeRecord::SIZE,
+        payer = payer,
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct WithdrawVested<'info> {
+    pub claimer: Signer<'info>,
+
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    #[account(mut)]
+    pub vesting: Account<'info, VestingSchedule>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        mut,
+        constraint = reward_vault.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = reward_vault.owner == global_authority.key(),
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_reward_account.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+    )]
+    pub user_reward_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct MintReceipt<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    pub nft_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Checked against the metadata address of nft_mint
+    pub mint_metadata: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [RECEIPT_SEED.as_ref(), receipt_mint.key().as_ref()],
+        bump,
+        space = 112,
+        payer = owner,
+    )]
+    pub receipt: Box<Account<'info, StakeReceipt>>,
+
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = global_authority,
+    )]
+    pub receipt_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = owner,
+    )]
+    pub receipt_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Created by the token metadata program
+    pub receipt_metadata: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: Handed to the receipt when it exists, bears staked before stake records have none
+    pub stake_record: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Ranked on once the admin created it, checked against the user pool's pool id
+    pub leaderboard: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [SET_BONUS_SEED.as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a SetBonusConfig once the admin created it
+    pub set_bonus_config: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    #[account(constraint = token_metadata_program.key == &mpl_token_metadata::ID)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct UnstakeReceipt<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    #[account(
+        mut,
+        seeds = [RECEIPT_SEED.as_ref(), receipt_mint.key().as_ref()],
+        bump,
+        close = holder,
+    )]
+    pub receipt: Box<Account<'info, StakeReceipt>>,
+
+    #[account(mut)]
+    pub receipt_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = receipt_account.mint == receipt_mint.key(),
+        constraint = receipt_account.owner == holder.key(),
+        constraint = receipt_account.amount == 1,
+    )]
+    pub receipt_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = nft_mint.key() == receipt.bear_mint)]
+    pub nft_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_bear_account.mint == nft_mint.key(),
+        constraint = user_bear_account.owner == holder.key(),
+    )]
+    pub user_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = dest_bear_account.mint == nft_mint.key(),
+        constraint = dest_bear_account.owner == global_authority.key(),
+        constraint = dest_bear_account.amount == 1,
+    )]
+    pub dest_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub user_box_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the receipt when it holds a box
+    pub dest_box_account: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = reward_vault.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = reward_vault.owner == global_authority.key(),
+    )]
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_reward_account.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = user_reward_account.owner == holder.key(),
+    )]
+    pub user_reward_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: Closed when it exists, bears staked before stake records have none
+    pub stake_record: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED.as_ref()],
+        bump,
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeBear<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    pub bear_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        constraint = user_bear_account.mint == bear_mint.key(),
+        constraint = user_bear_account.owner == owner.key(),
+        constraint = user_bear_account.amount == 1,
+    )]
+    pub user_bear_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Checked against the metadata PDA of bear_mint
+    pub bear_metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub box_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_box_account.mint == box_mint.key(),
+        constraint = user_box_account.owner == owner.key(),
+        constraint = user_box_account.amount == 1,
+    )]
+    pub user_box_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    /// CHECK: Checked against the metadata PDA of box_mint
+    pub box_metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Checked by the token metadata program
+    pub box_edition: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        seeds = [UPGRADE_SEED.as_ref(), bear_mint.key().as_ref()],
+        bump,
+        space = 56,
+        payer = owner,
+    )]
+    pub bear_upgrade: Box<Account<'info, BearUpgrade>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    #[account(constraint = token_metadata_program.key == &mpl_token_metadata::ID)]
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ListForRent<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
+
+    pub user_pool: AccountLoader<'info, UserPool>,
+
+    /// CHECK: Must be staked in the user pool
+    pub nft_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [RENTAL_SEED.as_ref(), nft_mint.key().as_ref(), owner.key().as_ref()],
+        bump,
+        space = 161,
+        payer = owner,
+    )]
+    pub listing: Box<Account<'info, RentalListing>>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(bump: u8)]
-pub struct StakeNft<'info> {
+pub struct CancelRentalListing<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
     #[account(
         mut,
+        seeds = [RENTAL_SEED.as_ref(), listing.nft_mint.as_ref(), owner.key().as_ref()],
+        bump,
+        close = owner,
+    )]
+    pub listing: Box<Account<'info, RentalListing>>,
+}
+
+#[derive(Accounts)]
+pub struct RentNft<'info> {
+    #[account(mut)]
+    pub renter: Signer<'info>,
+
+    #[account(mut, address = listing.owner)]
+    pub owner: SystemAccount<'info>,
+
+    #[account(
         seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
         bump,
     )]
     pub global_authority: Box<Account<'info, GlobalPool>>,
 
+    #[account(
+        mut,
+        seeds = [RENTAL_SEED.as_ref(), listing.nft_mint.as_ref(), listing.owner.as_ref()],
+        bump,
+    )]
+    pub listing: Box<Account<'info, RentalListing>>,
+
     #[account(mut)]
     pub user_pool: AccountLoader<'info, UserPool>,
 
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub nft_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
 
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub nft_box_mint: Account<'info, Mint>,
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct ClaimRentalReward<'info> {
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
 
     #[account(
         mut,
-        constraint = user_bear_account.mint == *nft_mint.to_account_info().key,
-        constraint = user_bear_account.owner == *owner.key,
-        constraint = user_bear_account.amount == 1,
+        seeds = [RENTAL_SEED.as_ref(), listing.nft_mint.as_ref(), listing.owner.as_ref()],
+        bump,
     )]
-    pub user_bear_account: Box<Account<'info, TokenAccount>>,
+    pub listing: Box<Account<'info, RentalListing>>,
 
     #[account(
         mut,
-        constraint = dest_bear_account.mint == *nft_mint.to_account_info().key,
-        constraint = dest_bear_account.owner == *global_authority.to_account_info().key,
+        constraint = reward_vault.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = reward_vault.owner == global_authority.key(),
     )]
-    pub dest_bear_account: Box<Account<'info, TokenAccount>>,
+    pub reward_vault: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut)]
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub user_box_account: AccountInfo<'info>,
+    #[account(
+        mut,
+        constraint = renter_reward_account.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = renter_reward_account.owner == listing.renter,
+    )]
+    pub renter_reward_account: Box<Account<'info, TokenAccount>>,
 
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool_id: u64)]
+pub struct CreatePool<'info> {
     #[account(mut)]
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub dest_box_account: AccountInfo<'info>,
+    pub admin: Signer<'info>,
 
     #[account(
-        mut,
-        constraint = reward_vault.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
-        constraint = reward_vault.owner == global_authority.key(),
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
     )]
-    pub reward_vault: Box<Account<'info, TokenAccount>>,
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        init,
+        seeds = [POOL_SEED.as_ref(), pool_id.to_le_bytes().as_ref()],
+        bump,
+        space = 224,
+        payer = admin,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    pub reward_mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    pub admin: Signer<'info>,
 
     #[account(
         mut,
-        constraint = user_reward_account.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
-        constraint = user_reward_account.owner == owner.key(),
+        seeds = [POOL_SEED.as_ref(), pool.pool_id.to_le_bytes().as_ref()],
+        bump,
     )]
-    pub user_reward_account: Box<Account<'info, TokenAccount>>,
+    pub pool: Account<'info, Pool>,
+}
 
+#[derive(Accounts)]
+#[instruction(pool_id: u64)]
+pub struct CreateLeaderboard<'info> {
     #[account(mut)]
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub mint_metadata: AccountInfo<'info>,
+    pub admin: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
-    #[account(constraint = token_metadata_program.key == &mpl_token_metadata::ID)]
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub token_metadata_program: AccountInfo<'info>,
+    #[account(
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Account<'info, GlobalPool>,
+
+    #[account(
+        init,
+        seeds = [LEADERBOARD_SEED.as_ref(), pool_id.to_le_bytes().as_ref()],
+        bump,
+        space = 1456,
+        payer = admin,
+    )]
+    pub leaderboard: Box<Account<'info, Leaderboard>>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(
-    bump: u8,
-)]
-pub struct UnstakeNft<'info> {
+pub struct InitPoolUser<'info> {
+    #[account(zero)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
     #[account(mut)]
     pub owner: Signer<'info>,
 
+    #[account(
+        seeds = [POOL_SEED.as_ref(), pool.pool_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct StakePoolNft<'info> {
     #[account(mut)]
-    pub user_pool: AccountLoader<'info, UserPool>,
+    pub owner: Signer<'info>,
 
     #[account(
         mut,
@@ -413,74 +2992,334 @@ pub struct UnstakeNft<'info> {
     )]
     pub global_authority: Box<Account<'info, GlobalPool>>,
 
-    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), pool.pool_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
+
     pub nft_mint: Account<'info, Mint>,
 
     /// CHECK: This is not dangerous because we don't read or write from this account
-    pub nft_box_mint: Account<'info, Mint>,
+    pub nft_box_mint: AccountInfo<'info>,
 
     #[account(
         mut,
         constraint = user_bear_account.mint == *nft_mint.to_account_info().key,
         constraint = user_bear_account.owner == *owner.key,
+        constraint = user_bear_account.amount == 1,
     )]
     pub user_bear_account: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
         constraint = dest_bear_account.mint == *nft_mint.to_account_info().key,
-        constraint = dest_bear_account.owner == *global_authority.to_account_info().key,
-        constraint = dest_bear_account.amount == 1,
+        constraint = dest_bear_account.owner == pool.key(),
     )]
     pub dest_bear_account: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut)]
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub user_box_account: AccountInfo<'info>,
+    /// CHECK: Checked against the metadata PDA of nft_mint
+    pub mint_metadata: AccountInfo<'info>,
 
-    #[account(mut)]
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub dest_box_account: AccountInfo<'info>,
+    #[account(
+        seeds = [UPGRADE_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: Read as a BearUpgrade once the bear was upgraded
+    pub bear_upgrade: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
+        bump,
+        space = StakeRecord::SIZE,
+        payer = owner,
+    )]
+    pub stake_record: Box<Account<'info, StakeRecord>>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(bump: u8)]
-pub struct ClaimReward<'info> {
+pub struct UnstakePoolNft<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
 
+    #[account(
+        mut,
+        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        bump,
+    )]
+    pub global_authority: Box<Account<'info, GlobalPool>>,
+
+    #[account(
+        mut,
+        seeds = [POOL_SEED.as_ref(), pool.pool_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
     #[account(mut)]
     pub user_pool: AccountLoader<'info, UserPool>,
 
+    pub nft_mint: Account<'info, Mint>,
+
     #[account(
         mut,
-        seeds = [GLOBAL_AUTHORITY_SEED.as_ref()],
+        constraint = user_bear_account.mint == *nft_mint.to_account_info().key,
+        constraint = user_bear_account.owner == *owner.key,
+    )]
+    pub user_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = dest_bear_account.mint == *nft_mint.to_account_info().key,
+        constraint = dest_bear_account.owner == pool.key(),
+        constraint = dest_bear_account.amount == 1,
+    )]
+    pub dest_bear_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [STAKE_RECORD_SEED.as_ref(), nft_mint.key().as_ref()],
         bump,
     )]
-    pub global_authority: Account<'info, GlobalPool>,
+    /// CHECK: Closed when it exists, bears staked before stake records have none
+    pub stake_record: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: Ranked on once the admin created it, checked against the user pool's pool id
+    pub leaderboard: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct ClaimPoolReward<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_SEED.as_ref(), pool.pool_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut)]
+    pub user_pool: AccountLoader<'info, UserPool>,
 
     #[account(
         mut,
-        constraint = reward_vault.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
-        constraint = reward_vault.owner == global_authority.key(),
+        constraint = reward_vault.mint == pool.reward_mint,
+        constraint = reward_vault.owner == pool.key(),
     )]
     pub reward_vault: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
-        constraint = user_reward_account.mint == MEDAL_TOKEN_ADDRESS.parse::<Pubkey>().unwrap(),
+        constraint = user_reward_account.mint == pool.reward_mint,
         constraint = user_reward_account.owner == owner.key(),
     )]
     pub user_reward_account: Box<Account<'info, TokenAccount>>,
 
+    #[account(mut)]
+    /// CHECK: Ranked on once the admin created it, checked against the user pool's pool id
+    pub leaderboard: UncheckedAccount<'info>,
+
     pub token_program: Program<'info, Token>,
 }
 
+fn verify_nft(mint_metadata: &AccountInfo, nft_mint: &Pubkey, collection: &Pubkey) -> Result<u64> {
+    msg!("Metadata Account: {:?}", mint_metadata.key());
+    let (metadata, _) = Pubkey::find_program_address(
+        &[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            nft_mint.as_ref(),
+        ],
+        &mpl_token_metadata::id(),
+    );
+    require!(
+        metadata == mint_metadata.key(),
+        StakingError::InvalidMetadata
+    );
+
+    let nft_metadata = Metadata::from_account_info(mint_metadata)?;
+
+    if let Some(creators) = nft_metadata.data.creators {
+        let mut valid: u8 = 0;
+        for creator in creators {
+            if creator.address == *collection && creator.verified {
+                valid = 1;
+                break;
+            }
+        }
+        require!(valid == 1, StakingError::UnkownOrNotAllowedNFTCollection);
+        msg!("Collection= {:?}", collection);
+    } else {
+        return Err(Error::from(StakingError::MetadataCreatorParseError));
+    }
+
+    let id = parse_nft_id(&nft_metadata.data.name)?;
+    msg!("NFT ID: {}", id);
+
+    Ok(id)
+}
+
+/// Number after the `#` of an NFT name, which the token metadata program pads with NUL bytes
+fn parse_nft_id(name: &str) -> Result<u64> {
+    let (_, id) = name.split_once('#').ok_or(StakingError::InvalidMetadata)?;
+    id.split('\0')
+        .next()
+        .unwrap_or_default()
+        .parse::<u64>()
+        .map_err(|_| error!(StakingError::InvalidMetadata))
+}
+
+fn collect_fee<'info>(
+    payer: AccountInfo<'info>,
+    sol_vault: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    fee: u64,
+) -> Result<()> {
+    if fee == 0 {
+        return Ok(());
+    }
+    let cpi_accounts = system_program::Transfer {
+        from: payer,
+        to: sol_vault,
+    };
+    system_program::transfer(CpiContext::new(system_program, cpi_accounts), fee)
+}
+
+/// Set bonus `user_pool` qualifies for, none until the config is created
+fn set_bonus_bps(set_bonus_config: &AccountInfo, user_pool: &UserPool) -> Result<u64> {
+    if set_bonus_config.data_is_empty() {
+        return Ok(0);
+    }
+    let config = Account::<SetBonusConfig>::try_from(set_bonus_config)?;
+    Ok(config.bonus_bps(user_pool))
+}
+
+/// Rate of a bear raised by its box upgrades, unchanged until it has any
+fn upgraded_reward_rate(bear_upgrade: &AccountInfo, reward_rate: u64) -> Result<u64> {
+    if bear_upgrade.data_is_empty() {
+        return Ok(reward_rate);
+    }
+    let bear_upgrade = Account::<BearUpgrade>::try_from(bear_upgrade)?;
+    Ok(upgraded_rate(reward_rate, bear_upgrade.tiers))
+}
+
+/// Close the stake record of an unstaked bear, refunding its rent to `destination`
+fn close_stake_record(stake_record: &AccountInfo, destination: &AccountInfo) -> Result<()> {
+    if stake_record.owner != &crate::ID {
+        return Ok(());
+    }
+    if let Ok(record) = StakeRecord::try_deserialize(&mut &stake_record.try_borrow_data()?[..]) {
+        require!(
+            !record.is_vote_locked(Clock::get()?.unix_timestamp),
+            StakingError::VoteLocked
+        );
+    }
+    **destination.try_borrow_mut_lamports()? += stake_record.lamports();
+    **stake_record.try_borrow_mut_lamports()? = 0;
+    stake_record.assign(&system_program::ID);
+    stake_record.realloc(0, false)?;
+    Ok(())
+}
+
+/// Hand the stake record of `nft_mint` over to a new owner when it exists
+fn move_stake_record(stake_record: &AccountInfo, nft_mint: &Pubkey, owner: Pubkey) -> Result<()> {
+    if let Some(mut record) = StakeRecord::load(stake_record, nft_mint) {
+        require!(
+            !record.is_vote_locked(Clock::get()?.unix_timestamp),
+            StakingError::VoteLocked
+        );
+        record.owner = owner;
+        let mut data = stake_record.try_borrow_mut_data()?;
+        record.try_serialize(&mut &mut data[..])?;
+    }
+    Ok(())
+}
+
+/// Rank the user on the leaderboard of its pool once the admin created it
+fn record_points(
+    leaderboard: &AccountInfo,
+    user_pool_key: Pubkey,
+    user_pool: &UserPool,
+) -> Result<()> {
+    if leaderboard.data_is_empty() {
+        return Ok(());
+    }
+    let mut leaderboard = Account::<Leaderboard>::try_from(leaderboard)?;
+    require!(
+        leaderboard.pool_id == user_pool.pool_id,
+        StakingError::InvalidLeaderboard
+    );
+    leaderboard.record(user_pool_key, user_pool.owner, user_pool.points);
+    leaderboard.exit(&crate::ID)
+}
+
 // Access control modifiers
+fn admin(global_authority: &Account<GlobalPool>, admin: &AccountInfo) -> Result<()> {
+    require!(
+        global_authority.super_admin == *admin.key,
+        StakingError::InvalidSuperAdmin
+    );
+    Ok(())
+}
+
+fn current_version(pool_loader: &AccountLoader<UserPool>) -> Result<()> {
+    require!(
+        pool_loader.to_account_info().data_len() >= 8 + std::mem::size_of::<UserPool>(),
+        StakingError::UserPoolNeedsMigration
+    );
+    require!(
+        pool_loader.load()?.version == USER_POOL_VERSION,
+        StakingError::UserPoolNeedsMigration
+    );
+    Ok(())
+}
+
 fn user(pool_loader: &AccountLoader<UserPool>, user: &AccountInfo) -> Result<()> {
+    current_version(pool_loader)?;
+    let user_pool = pool_loader.load()?;
+    require!(
+        user_pool.owner == *user.key && user_pool.pool_id == 0,
+        StakingError::InvalidUserPool
+    );
+    Ok(())
+}
+
+fn pool_user(pool_loader: &AccountLoader<UserPool>, user: &AccountInfo, pool: &Pool) -> Result<()> {
+    current_version(pool_loader)?;
+    let user_pool = pool_loader.load()?;
+    require!(
+        user_pool.owner == *user.key && user_pool.pool_id == pool.pool_id,
+        StakingError::InvalidUserPool
+    );
+    Ok(())
+}
+
+fn pool_admin(pool: &Account<Pool>, admin: &AccountInfo) -> Result<()> {
+    require!(pool.admin == *admin.key, StakingError::InvalidPoolAdmin);
+    Ok(())
+}
+
+fn claimer(pool_loader: &AccountLoader<UserPool>, claimer: &AccountInfo) -> Result<()> {
+    current_version(pool_loader)?;
     let user_pool = pool_loader.load()?;
-    require!(user_pool.owner == *user.key, StakingError::InvalidUserPool);
+    require!(user_pool.pool_id == 0, StakingError::InvalidUserPool);
+    require!(
+        user_pool.can_claim(claimer.key),
+        StakingError::InvalidClaimAuthority
+    );
     Ok(())
 }
@@ -0,0 +1,309 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+use crate::account::{EmissionSchedule, RateChange, RewardTokenConfig, StakedData};
+use crate::constant::*;
+use crate::error::*;
+
+/// Daily MEDAL rate of a bear in whole tokens, looked up by its mint number
+pub fn bear_rate(bear_id: u64) -> Result<u64> {
+    let rate = match bear_id {
+        1..=1920 | 8001..=8480 => 10,
+        1921..=3520 | 8481..=8880 => 14,
+        3521..=4880 | 8881..=9220 => 17,
+        4881..=6000 | 9221..=9500 => 20,
+        6001..=6880 | 9501..=9720 => 22,
+        6881..=7520 | 9721..=9880 => 24,
+        7521..=7920 | 9881..=9980 => 26,
+        7921..=7992 | 9981..=9998 => 30,
+        7993..=8000 | 9999..=10000 => 100,
+        _ => return Err(StakingError::NotAllowedNFTID.into()),
+    };
+    Ok(rate)
+}
+
+/// Box bonus expressed in MEDAL base units per whole token of bear rate
+pub fn box_multiplier(box_id: u64) -> u64 {
+    match box_id {
+        1..=5000 => 1_100_000_000,
+        5001..=8000 => 1_200_000_000,
+        8001..=9500 => 1_300_000_000,
+        9501..=10000 => 1_500_000_000,
+        _ => 1_000_000_000,
+    }
+}
+
+/// Daily reward of a bear/box pair in MEDAL base units
+pub fn reward_rate(bear_id: u64, box_id: u64) -> Result<u64> {
+    Ok(bear_rate(bear_id)? * box_multiplier(box_id))
+}
+
+/// Daily reward of a staked NFT, preferring the rarity rate verified at stake time
+pub fn staked_reward_rate(staked: &StakedData) -> Result<u64> {
+    let rate = if staked.reward_rate != 0 {
+        staked.reward_rate
+    } else {
+        bear_rate(staked.bear_id)?
+    };
+    Ok(rate
+        .checked_mul(box_multiplier(staked.box_id))
+        .ok_or(StakingError::MathOverflow)?)
+}
+
+/// Streak bonus of a bear staked since `staked_time`, in bps on top of its rate
+pub fn streak_bonus_bps(staked_time: i64, now: i64) -> u64 {
+    STREAK_TIERS
+        .iter()
+        .rev()
+        .find(|(after, _)| now - staked_time >= *after)
+        .map_or(0, |(_, bps)| *bps)
+}
+
+/// Add up `accrue` over `from..to`, split where the streak of a bear staked at `staked_time`
+/// moves up a tier so every part is raised by the bonus of its own tier
+pub fn streak_accrued(
+    staked_time: i64,
+    from: i64,
+    to: i64,
+    accrue: impl Fn(i64, i64) -> Result<u64>,
+) -> Result<u64> {
+    let mut total: u128 = 0;
+    for (i, (after, bps)) in STREAK_TIERS.iter().enumerate() {
+        let tier_end = STREAK_TIERS
+            .get(i + 1)
+            .map_or(i64::MAX, |(next, _)| staked_time + next);
+        let (start, end) = (from.max(staked_time + after), to.min(tier_end));
+        if start < end {
+            total +=
+                accrue(start, end)? as u128 * (BPS_DENOMINATOR + *bps as u128) / BPS_DENOMINATOR;
+        }
+    }
+    u64::try_from(total).map_err(|_| StakingError::MathOverflow.into())
+}
+
+/// `streak_accrued` of a staked bear over `from..to`, leaving out the window it is rented out
+pub fn owner_accrued(
+    staked: &StakedData,
+    from: i64,
+    to: i64,
+    accrue: impl Fn(i64, i64) -> Result<u64>,
+) -> Result<u64> {
+    if staked.rent_end <= staked.rent_start {
+        return streak_accrued(staked.staked_time, from, to, accrue);
+    }
+    let before = streak_accrued(staked.staked_time, from, to.min(staked.rent_start), &accrue)?;
+    let after = streak_accrued(staked.staked_time, from.max(staked.rent_end), to, &accrue)?;
+    before
+        .checked_add(after)
+        .ok_or_else(|| StakingError::MathOverflow.into())
+}
+
+/// Leaf of the rarity table: keccak(mint || rate as little endian)
+pub fn rarity_leaf(mint: &Pubkey, rate: u64) -> [u8; 32] {
+    keccak::hashv(&[mint.as_ref(), &rate.to_le_bytes()]).0
+}
+
+/// Verify a (mint, rate) pair against the published root, hashing each pair in sorted order
+pub fn verify_rarity(root: &[u8; 32], mint: &Pubkey, rate: u64, proof: &[[u8; 32]]) -> bool {
+    let mut node = rarity_leaf(mint, rate);
+    for sibling in proof {
+        node = if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).0
+        } else {
+            keccak::hashv(&[sibling, &node]).0
+        };
+    }
+    node == *root
+}
+
+/// Tier of a staked bear in `BEAR_TIER_RATES`, if its rate is one of them
+pub fn bear_tier(staked: &StakedData) -> Option<usize> {
+    let rate = if staked.reward_rate != 0 {
+        staked.reward_rate
+    } else {
+        bear_rate(staked.bear_id).ok()?
+    };
+    BEAR_TIER_RATES.iter().position(|r| *r == rate)
+}
+
+/// Voting power of a staked bear: its daily rate in whole MEDAL, raised linearly by up to
+/// `duration_bonus_bps` over its first `duration_cap` seconds staked. A zero cap ignores duration.
+pub fn voting_power(
+    rate: u64,
+    staked_time: i64,
+    now: i64,
+    duration_bonus_bps: u64,
+    duration_cap: i64,
+) -> Result<u64> {
+    if duration_cap <= 0 {
+        return Ok(rate);
+    }
+    let staked_for = (now - staked_time).clamp(0, duration_cap);
+    let bps = duration_bonus_bps as u128 * staked_for as u128 / duration_cap as u128;
+    apply_bonus(rate, bps as u64)
+}
+
+/// `rate` raised `tiers` steps up `BEAR_TIER_RATES`, capped at `MAX_UPGRADE_TIER` and never
+/// lowered, so rarity rates off the table keep whatever is higher
+pub fn upgraded_rate(rate: u64, tiers: u64) -> u64 {
+    if tiers == 0 {
+        return rate;
+    }
+    let tier = BEAR_TIER_RATES
+        .iter()
+        .rposition(|r| *r <= rate)
+        .unwrap_or(0);
+    let upgraded = tier.saturating_add(tiers as usize).min(MAX_UPGRADE_TIER);
+    rate.max(BEAR_TIER_RATES[upgraded])
+}
+
+/// Raise `amount` by `bps`
+pub fn apply_bonus(amount: u64, bps: u64) -> Result<u64> {
+    let raised = amount as u128 * (BPS_DENOMINATOR + bps as u128) / BPS_DENOMINATOR;
+    u64::try_from(raised).map_err(|_| StakingError::MathOverflow.into())
+}
+
+/// Reward accrued at a constant `rate` (base units per day) between `from` and `to`
+pub fn linear_accrued(rate: u64, from: i64, to: i64) -> Result<u64> {
+    if to <= from {
+        return Ok(0);
+    }
+    let reward = rate as u128 * (to - from) as u128 / ONE_DAY as u128;
+    u64::try_from(reward).map_err(|_| StakingError::MathOverflow.into())
+}
+
+impl RewardTokenConfig {
+    /// Pay `rate_per_medal` from `now` on, keeping the old rate for what accrued before
+    pub fn set_rate(&mut self, rate_per_medal: u64, now: i64) -> Result<()> {
+        if rate_per_medal == self.rate_per_medal {
+            return Ok(());
+        }
+        let change = self
+            .previous_rates
+            .iter_mut()
+            .find(|change| change.until == 0)
+            .ok_or(StakingError::RateHistoryFull)?;
+        *change = RateChange {
+            rate_per_medal: self.rate_per_medal,
+            until: now,
+        };
+        self.rate_per_medal = rate_per_medal;
+        Ok(())
+    }
+
+    /// Reward of a bear earning `medal_rate` MEDAL per day between `from` and `to`,
+    /// at the rates in force over that time
+    pub fn accrued(&self, medal_rate: u64, from: i64, to: i64) -> Result<u64> {
+        let current = RateChange {
+            rate_per_medal: self.rate_per_medal,
+            until: i64::MAX,
+        };
+        let mut total: u64 = 0;
+        let mut start = from;
+        for change in self
+            .previous_rates
+            .iter()
+            .filter(|change| change.until != 0)
+            .chain(std::iter::once(&current))
+        {
+            let end = change.until.min(to);
+            if end > start {
+                let rate = (medal_rate as u128 * change.rate_per_medal as u128 / EMISSION_PRECISION)
+                    as u64;
+                total = total
+                    .checked_add(linear_accrued(rate, start, end)?)
+                    .ok_or(StakingError::MathOverflow)?;
+                start = end;
+            }
+        }
+        Ok(total)
+    }
+}
+
+/// Rate of a bear from the published rarity table, or from the id ranges when there is none
+pub fn resolve_reward_rate(
+    rarity_root: &[u8; 32],
+    mint: &Pubkey,
+    bear_id: u64,
+    rarity_rate: u64,
+    rarity_proof: &[[u8; 32]],
+) -> Result<u64> {
+    if *rarity_root == [0u8; 32] {
+        return bear_rate(bear_id);
+    }
+    require!(
+        rarity_rate != 0 && verify_rarity(rarity_root, mint, rarity_rate, rarity_proof),
+        StakingError::InvalidRarityProof
+    );
+    Ok(rarity_rate)
+}
+
+impl EmissionSchedule {
+    /// Emission multiplier of the epoch `epochs` after the start, in `EMISSION_PRECISION`
+    fn decayed(&self, epochs: u64) -> u128 {
+        let mut result = EMISSION_PRECISION;
+        let mut base = self.decay_bps as u128 * EMISSION_PRECISION / BPS_DENOMINATOR;
+        let mut exp = epochs;
+        while exp > 0 && result > 0 {
+            if exp & 1 == 1 {
+                result = result * base / EMISSION_PRECISION;
+            }
+            base = base * base / EMISSION_PRECISION;
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// Emission of the first `epochs` whole epochs, in seconds * `EMISSION_PRECISION`
+    fn full_epochs(&self, epochs: u64) -> u128 {
+        let length = self.epoch_length as u128;
+        let ratio = self.decay_bps as u128 * EMISSION_PRECISION / BPS_DENOMINATOR;
+        if ratio >= EMISSION_PRECISION {
+            epochs as u128 * length * EMISSION_PRECISION
+        } else {
+            // Geometric series: length * (1 - d^n) / (1 - d)
+            length * (EMISSION_PRECISION - self.decayed(epochs)) * EMISSION_PRECISION
+                / (EMISSION_PRECISION - ratio)
+        }
+    }
+
+    /// Integral of the emission multiplier from `start_time` to `time`, in seconds * `EMISSION_PRECISION`.
+    /// Time before the schedule starts is emitted at the full rate.
+    pub fn emitted(&self, time: i64) -> i128 {
+        let precision = EMISSION_PRECISION as i128;
+        if self.epoch_length <= 0 || time <= self.start_time {
+            return (time as i128 - self.start_time as i128) * precision;
+        }
+
+        let elapsed = (time - self.start_time) as u128;
+        let length = self.epoch_length as u128;
+        let epochs = (elapsed / length) as u64;
+        let remainder = elapsed % length;
+
+        // The series rounds differently from the per-epoch multiplier, so cap the partial
+        // epoch at the next boundary to keep the integral from ever going backwards
+        let emitted = self.full_epochs(epochs) + remainder * self.decayed(epochs);
+        emitted.min(self.full_epochs(epochs + 1)) as i128
+    }
+
+    /// Whether the decay already applies at `now`, until then everything accrued at the full rate
+    pub fn started(&self, now: i64) -> bool {
+        self.epoch_length > 0 && now > self.start_time
+    }
+
+    /// Whether `next` can replace this schedule at `now` without re-pricing accrual up to `now`.
+    /// Neither may have decayed anything yet, so both emitted at the full rate so far.
+    pub fn can_replace(&self, next: &EmissionSchedule, now: i64) -> bool {
+        self == next || (!self.started(now) && (next.epoch_length <= 0 || next.start_time >= now))
+    }
+
+    /// Reward accrued at `rate` (base units per day) between `from` and `to`
+    pub fn accrued(&self, rate: u64, from: i64, to: i64) -> Result<u64> {
+        if to <= from {
+            return Ok(0);
+        }
+        let emitted = (self.emitted(to) - self.emitted(from)) as u128;
+        let reward = rate as u128 * emitted / (EMISSION_PRECISION * ONE_DAY as u128);
+        u64::try_from(reward).map_err(|_| StakingError::MathOverflow.into())
+    }
+}
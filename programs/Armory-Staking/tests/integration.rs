@@ -0,0 +1,658 @@
+//! End to end tests booting the program in `solana-program-test`.
+//!
+//! Staking only reads Token Metadata accounts, so bear and box metadata with verified
+//! creators are injected directly. The Token Metadata program itself is loaded from
+//! `tests/fixtures/mpl_token_metadata.so` when present, dumped with
+//! `solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s tests/fixtures/mpl_token_metadata.so`.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use armory_staking::{account::*, constant::*};
+use mpl_token_metadata::state::{Creator, Data, Key, Metadata, MAX_METADATA_LEN, PREFIX};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::Transaction,
+};
+
+const VAULT_FUNDING: u64 = 1_000_000_000_000;
+
+struct Nft {
+    mint: Pubkey,
+    metadata: Pubkey,
+    user_account: Pubkey,
+    escrow_account: Pubkey,
+}
+
+struct Env {
+    context: ProgramTestContext,
+    user: Keypair,
+    global_authority: Pubkey,
+    global_bump: u8,
+    sol_vault: Pubkey,
+    set_bonus_config: Pubkey,
+    leaderboard: Pubkey,
+    vesting: Pubkey,
+    reward_vault: Pubkey,
+    user_reward_account: Pubkey,
+    user_pool: Pubkey,
+    bear: Nft,
+    bear_box: Nft,
+}
+
+fn clone_keypair(keypair: &Keypair) -> Keypair {
+    Keypair::from_bytes(&keypair.to_bytes()).unwrap()
+}
+
+fn medal_mint() -> Pubkey {
+    MEDAL_TOKEN_ADDRESS.parse().unwrap()
+}
+
+fn add_mint(program_test: &mut ProgramTest, mint: Pubkey, authority: &Pubkey, decimals: u8) {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(*authority),
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(
+        mint,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+fn add_token_account(
+    program_test: &mut ProgramTest,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(
+        address,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    address
+}
+
+/// Bank timestamps drift a little between slots, so allow a minute of accrual
+fn assert_reward(actual: u64, expected: u64, daily_reward: u64) {
+    let tolerance = daily_reward / ONE_DAY as u64 * 60;
+    assert!(
+        actual + tolerance >= expected && actual <= expected + tolerance,
+        "reward {} not within {} of {}",
+        actual,
+        tolerance,
+        expected
+    );
+}
+
+/// Mint a mock NFT held by `owner`, with metadata verified by `collection`
+fn add_nft(
+    program_test: &mut ProgramTest,
+    owner: &Pubkey,
+    global_authority: &Pubkey,
+    collection: &str,
+    name: &str,
+) -> Nft {
+    let mint = Pubkey::new_unique();
+    add_mint(program_test, mint, owner, 0);
+
+    let (metadata, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            mint.as_ref(),
+        ],
+        &mpl_token_metadata::id(),
+    );
+    let mut data = vec![0; MAX_METADATA_LEN];
+    Metadata {
+        key: Key::MetadataV1,
+        update_authority: *owner,
+        mint,
+        data: Data {
+            name: format!("{:\0<32}", name),
+            symbol: String::new(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: Some(vec![Creator {
+                address: collection.parse().unwrap(),
+                verified: true,
+                share: 100,
+            }]),
+        },
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: None,
+        collection: None,
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    }
+    .save(&mut data)
+    .unwrap();
+    program_test.add_account(
+        metadata,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: mpl_token_metadata::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    Nft {
+        mint,
+        metadata,
+        user_account: add_token_account(program_test, &mint, owner, 1),
+        escrow_account: add_token_account(program_test, &mint, global_authority, 0),
+    }
+}
+
+async fn setup() -> Env {
+    let mut program_test = ProgramTest::new(
+        "armory_staking",
+        armory_staking::ID,
+        processor!(armory_staking::entry),
+    );
+    if std::path::Path::new("tests/fixtures/mpl_token_metadata.so").exists() {
+        program_test.add_program("mpl_token_metadata", mpl_token_metadata::id(), None);
+    }
+
+    let user = Keypair::new();
+    program_test.add_account(
+        user.pubkey(),
+        Account {
+            lamports: 10_000_000_000,
+            data: vec![],
+            owner: system_program::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (global_authority, global_bump) =
+        Pubkey::find_program_address(&[GLOBAL_AUTHORITY_SEED.as_bytes()], &armory_staking::ID);
+    let (sol_vault, _) =
+        Pubkey::find_program_address(&[SOL_VAULT_SEED.as_bytes()], &armory_staking::ID);
+    let (set_bonus_config, _) =
+        Pubkey::find_program_address(&[SET_BONUS_SEED.as_bytes()], &armory_staking::ID);
+    let (leaderboard, _) = Pubkey::find_program_address(
+        &[LEADERBOARD_SEED.as_bytes(), &0u64.to_le_bytes()],
+        &armory_staking::ID,
+    );
+    let (vesting, _) = Pubkey::find_program_address(
+        &[VESTING_SEED.as_bytes(), user.pubkey().as_ref()],
+        &armory_staking::ID,
+    );
+
+    add_mint(&mut program_test, medal_mint(), &user.pubkey(), 9);
+    let reward_vault = add_token_account(
+        &mut program_test,
+        &medal_mint(),
+        &global_authority,
+        VAULT_FUNDING,
+    );
+    let user_reward_account =
+        add_token_account(&mut program_test, &medal_mint(), &user.pubkey(), 0);
+
+    let bear = add_nft(
+        &mut program_test,
+        &user.pubkey(),
+        &global_authority,
+        BEAR_COLLECTION_ADDRESS,
+        "Bear #100",
+    );
+    let bear_box = add_nft(
+        &mut program_test,
+        &user.pubkey(),
+        &global_authority,
+        BOX_COLLECTION_ADDRESS,
+        "Box #6000",
+    );
+
+    let user_pool =
+        Pubkey::create_with_seed(&user.pubkey(), "user-pool", &armory_staking::ID).unwrap();
+
+    Env {
+        context: program_test.start_with_context().await,
+        user,
+        global_authority,
+        global_bump,
+        sol_vault,
+        set_bonus_config,
+        leaderboard,
+        vesting,
+        reward_vault,
+        user_reward_account,
+        user_pool,
+        bear,
+        bear_box,
+    }
+}
+
+async fn process(env: &mut Env, instructions: &[Instruction], signer: Option<&Keypair>) {
+    let blockhash = env
+        .context
+        .banks_client
+        .get_new_latest_blockhash(&env.context.last_blockhash)
+        .await
+        .unwrap();
+    env.context.last_blockhash = blockhash;
+
+    let mut signers = vec![&env.context.payer];
+    signers.extend(signer);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&env.context.payer.pubkey()),
+        &signers,
+        blockhash,
+    );
+    env.context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn warp(env: &mut Env, seconds: i64) {
+    let mut clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += seconds;
+    env.context.set_sysvar(&clock);
+}
+
+async fn token_amount(env: &mut Env, address: Pubkey) -> u64 {
+    let account = env
+        .context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+async fn global_pool(env: &mut Env) -> GlobalPool {
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.global_authority)
+        .await
+        .unwrap()
+        .unwrap();
+    GlobalPool::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn user_pool(env: &mut Env) -> UserPool {
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.user_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    bytemuck::pod_read_unaligned(&account.data[8..8 + std::mem::size_of::<UserPool>()])
+}
+
+async fn stake_record(env: &mut Env) -> Option<StakeRecord> {
+    let account = env
+        .context
+        .banks_client
+        .get_account(StakeRecord::address(&env.bear.mint))
+        .await
+        .unwrap()?;
+    Some(StakeRecord::try_deserialize(&mut account.data.as_slice()).unwrap())
+}
+
+async fn initialize(env: &mut Env) {
+    let admin = env.context.payer.pubkey();
+    let initialize = Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::Initialize {
+            admin,
+            global_authority: env.global_authority,
+            sol_vault: env.sol_vault,
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::Initialize {
+            _global_bump: env.global_bump,
+        }
+        .data(),
+    };
+
+    let owner = env.user.pubkey();
+    let space = 8 + std::mem::size_of::<UserPool>();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let create_user_pool = system_instruction::create_account_with_seed(
+        &owner,
+        &env.user_pool,
+        &owner,
+        "user-pool",
+        rent.minimum_balance(space),
+        space as u64,
+        &armory_staking::ID,
+    );
+    let init_user_pool = Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::InitUserPool {
+            user_pool: env.user_pool,
+            owner,
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::InitUserPool {}.data(),
+    };
+
+    let user = clone_keypair(&env.user);
+    process(
+        env,
+        &[initialize, create_user_pool, init_user_pool],
+        Some(&user),
+    )
+    .await;
+}
+
+fn stake_ix(env: &Env, box_id: u64) -> Instruction {
+    Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::StakeNft {
+            owner: env.user.pubkey(),
+            global_authority: env.global_authority,
+            user_pool: env.user_pool,
+            nft_mint: env.bear.mint,
+            nft_box_mint: env.bear_box.mint,
+            user_bear_account: env.bear.user_account,
+            dest_bear_account: env.bear.escrow_account,
+            user_box_account: env.bear_box.user_account,
+            dest_box_account: env.bear_box.escrow_account,
+            reward_vault: env.reward_vault,
+            user_reward_account: env.user_reward_account,
+            mint_metadata: env.bear.metadata,
+            bear_upgrade: BearUpgrade::address(&env.bear.mint),
+            stake_record: StakeRecord::address(&env.bear.mint),
+            set_bonus_config: env.set_bonus_config,
+            sol_vault: env.sol_vault,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+            token_metadata_program: mpl_token_metadata::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::StakeNft {
+            _global_bump: env.global_bump,
+            _box_id: box_id,
+            rarity_rate: 0,
+            rarity_proof: vec![],
+        }
+        .data(),
+    }
+}
+
+fn unstake_ix(env: &Env, box_id: u64, auto_claim: bool) -> Instruction {
+    let mut instruction = Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::UnstakeNft {
+            owner: env.user.pubkey(),
+            user_pool: env.user_pool,
+            global_authority: env.global_authority,
+            nft_mint: env.bear.mint,
+            nft_box_mint: env.bear_box.mint,
+            user_bear_account: env.bear.user_account,
+            dest_bear_account: env.bear.escrow_account,
+            user_box_account: env.bear_box.user_account,
+            dest_box_account: env.bear_box.escrow_account,
+            leaderboard: env.leaderboard,
+            stake_record: StakeRecord::address(&env.bear.mint),
+            set_bonus_config: env.set_bonus_config,
+            sol_vault: env.sol_vault,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::UnstakeNft {
+            _global_bump: env.global_bump,
+            _box_id: box_id,
+        }
+        .data(),
+    };
+    if auto_claim {
+        instruction.accounts.extend([
+            AccountMeta::new(env.reward_vault, false),
+            AccountMeta::new(env.user_reward_account, false),
+        ]);
+    }
+    instruction
+}
+
+fn claim_ix(env: &Env) -> Instruction {
+    Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::ClaimReward {
+            claimer: env.user.pubkey(),
+            user_pool: env.user_pool,
+            global_authority: env.global_authority,
+            reward_vault: env.reward_vault,
+            user_reward_account: env.user_reward_account,
+            vesting: env.vesting,
+            leaderboard: env.leaderboard,
+            sol_vault: env.sol_vault,
+            token_program: spl_token::id(),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::ClaimReward {
+            _global_bump: env.global_bump,
+            allow_partial: false,
+            instant: false,
+            reward_token_slots: vec![],
+        }
+        .data(),
+    }
+}
+
+#[tokio::test]
+async fn initialize_and_init_user_pool() {
+    let mut env = setup().await;
+    initialize(&mut env).await;
+
+    let global = global_pool(&mut env).await;
+    assert_eq!(global.super_admin, env.context.payer.pubkey());
+    assert_eq!(global.total_staked_count, 0);
+
+    let pool = user_pool(&mut env).await;
+    assert_eq!(pool.owner, env.user.pubkey());
+    assert_eq!(pool.version, USER_POOL_VERSION);
+    assert_eq!({ pool.staked_count }, 0);
+}
+
+#[tokio::test]
+async fn stake_claim_and_unstake_with_mission_bonus() {
+    let mut env = setup().await;
+    initialize(&mut env).await;
+    let user = clone_keypair(&env.user);
+
+    // Bear #100 earns 10 MEDAL a day, Box #6000 multiplies it by 1.2
+    let daily_reward = 12_000_000_000;
+
+    let ix = stake_ix(&env, 6000);
+    process(&mut env, &[ix], Some(&user)).await;
+
+    assert_eq!(token_amount(&mut env, env.bear.escrow_account).await, 1);
+    assert_eq!(token_amount(&mut env, env.bear_box.escrow_account).await, 1);
+    assert_eq!(
+        token_amount(&mut env, env.user_reward_account).await,
+        MISSION_BONUS
+    );
+    assert_eq!(global_pool(&mut env).await.total_staked_count, 1);
+    let pool = user_pool(&mut env).await;
+    assert_eq!({ pool.staked_count }, 1);
+    assert!(pool.mission_completed);
+    assert_eq!(pool.staked_nfts[0].bear_mint, env.bear.mint);
+    assert_eq!({ pool.staked_nfts[0].bear_id }, 100);
+    assert!({ pool.staked_nfts[0].staked_time } > 0);
+    let record = stake_record(&mut env).await.unwrap();
+    assert_eq!(record.owner, user.pubkey());
+    assert_eq!(record.staked_time, pool.staked_nfts[0].staked_time);
+    assert_eq!(record.tier, 0);
+    assert_eq!(record.box_id, 6000);
+
+    warp(&mut env, 2 * ONE_DAY).await;
+    let ix = claim_ix(&env);
+    process(&mut env, &[ix], Some(&user)).await;
+    assert_reward(
+        token_amount(&mut env, env.user_reward_account).await,
+        MISSION_BONUS + 2 * daily_reward,
+        daily_reward,
+    );
+
+    // Claiming again within a day is rejected while nothing is pending
+    let ix = claim_ix(&env);
+    let blockhash = env.context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&env.context.payer.pubkey()),
+        &[&env.context.payer, &user],
+        blockhash,
+    );
+    assert!(env
+        .context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .is_err());
+
+    // Unstaking with the reward accounts pays the last day right away
+    warp(&mut env, ONE_DAY).await;
+    let ix = unstake_ix(&env, 6000, true);
+    process(&mut env, &[ix], Some(&user)).await;
+
+    assert_eq!(token_amount(&mut env, env.bear.user_account).await, 1);
+    assert_eq!(token_amount(&mut env, env.bear_box.user_account).await, 1);
+    assert_eq!(token_amount(&mut env, env.bear.escrow_account).await, 0);
+    assert_reward(
+        token_amount(&mut env, env.user_reward_account).await,
+        MISSION_BONUS + 3 * daily_reward,
+        daily_reward,
+    );
+    let paid = token_amount(&mut env, env.user_reward_account).await;
+    assert_eq!(
+        token_amount(&mut env, env.reward_vault).await,
+        VAULT_FUNDING - paid
+    );
+    assert_eq!(global_pool(&mut env).await.total_staked_count, 0);
+    let pool = user_pool(&mut env).await;
+    assert_eq!({ pool.staked_count }, 0);
+    assert_eq!({ pool.pending_reward }, 0);
+    assert!(stake_record(&mut env).await.is_none());
+}
+
+#[tokio::test]
+async fn unstake_parks_rewards_until_claimed() {
+    let mut env = setup().await;
+    initialize(&mut env).await;
+    let user = clone_keypair(&env.user);
+    let daily_reward = 10_000_000_000;
+
+    let ix = stake_ix(&env, 0);
+    process(&mut env, &[ix], Some(&user)).await;
+    warp(&mut env, ONE_DAY / 2).await;
+
+    let ix = unstake_ix(&env, 0, false);
+    process(&mut env, &[ix], Some(&user)).await;
+    assert_reward(
+        user_pool(&mut env).await.pending_reward,
+        daily_reward / 2,
+        daily_reward,
+    );
+
+    // Pending rewards are not held back by the daily claim gate
+    let ix = claim_ix(&env);
+    process(&mut env, &[ix], Some(&user)).await;
+    assert_reward(
+        token_amount(&mut env, env.user_reward_account).await,
+        MISSION_BONUS + daily_reward / 2,
+        daily_reward,
+    );
+    assert_eq!({ user_pool(&mut env).await.pending_reward }, 0);
+}
+
+#[tokio::test]
+async fn backfill_recreates_the_record_of_a_bear_staked_before_records() {
+    let mut env = setup().await;
+    initialize(&mut env).await;
+    let user = clone_keypair(&env.user);
+
+    let ix = stake_ix(&env, 6000);
+    process(&mut env, &[ix], Some(&user)).await;
+    let staked = stake_record(&mut env).await.unwrap();
+
+    // A bear staked before stake records has its entry in the user pool only
+    env.context.set_account(
+        &StakeRecord::address(&env.bear.mint),
+        &solana_sdk::account::AccountSharedData::default(),
+    );
+    assert!(stake_record(&mut env).await.is_none());
+
+    let backfill = Instruction {
+        program_id: armory_staking::ID,
+        accounts: armory_staking::accounts::BackfillStakeRecord {
+            payer: env.context.payer.pubkey(),
+            user_pool: env.user_pool,
+            nft_mint: env.bear.mint,
+            stake_record: StakeRecord::address(&env.bear.mint),
+            system_program: system_program::id(),
+        }
+        .to_account_metas(None),
+        data: armory_staking::instruction::BackfillStakeRecord {}.data(),
+    };
+    process(&mut env, &[backfill], None).await;
+
+    let record = stake_record(&mut env).await.unwrap();
+    assert_eq!(record.owner, user.pubkey());
+    assert_eq!(record.nft_mint, env.bear.mint);
+    assert_eq!(record.pool_id, 0);
+    assert_eq!(record.staked_time, staked.staked_time);
+    assert_eq!(record.tier, staked.tier);
+    assert_eq!(record.box_mint, env.bear_box.mint);
+    assert_eq!(record.box_id, 6000);
+    assert_eq!(record.reward_rate, staked.reward_rate);
+}
@@ -0,0 +1,123 @@
+//! Migration of user pools created before versioning, built byte for byte from the original
+//! account definition.
+
+use anchor_lang::{prelude::Pubkey, Discriminator};
+use armory_staking::{account::*, constant::*};
+
+/// Account definitions as deployed before `migrate_user_pool`. Anchor 0.26 made zero copy
+/// accounts `repr(packed)` and implemented `Pod` for them; `repr(C)` pins the declared field
+/// order the deployed program was built with, newer compilers may move the bool to the front.
+mod v1 {
+    use anchor_lang::prelude::Pubkey;
+    use armory_staking::constant::STAKE_MAX_COUNT;
+
+    #[repr(C, packed)]
+    #[derive(Clone, Copy, Default)]
+    pub struct StakedData {
+        pub bear_mint: Pubkey, // 32
+        pub bear_id: u64,      // 8
+        pub box_mint: Pubkey,  // 32
+        pub box_id: u64,       // 8
+        pub staked_time: i64,  // 8
+    }
+
+    #[repr(C, packed)]
+    #[derive(Clone, Copy)]
+    pub struct UserPool {
+        pub owner: Pubkey,
+        pub last_claimed_time: i64,
+        pub pending_reward: u64,
+        pub staked_count: u64,
+        pub mission_completed: bool,
+        pub staked_nfts: [StakedData; STAKE_MAX_COUNT],
+    }
+
+    unsafe impl bytemuck::Zeroable for StakedData {}
+    unsafe impl bytemuck::Pod for StakedData {}
+    unsafe impl bytemuck::Zeroable for UserPool {}
+    unsafe impl bytemuck::Pod for UserPool {}
+}
+
+/// A v1 account with `staked_count` NFTs, allocated at the size the old script used
+fn v1_account(staked_count: usize) -> (v1::UserPool, Vec<u8>) {
+    let mut user_pool = v1::UserPool {
+        owner: Pubkey::new_unique(),
+        last_claimed_time: 1_650_000_000,
+        pending_reward: 123_456_789,
+        staked_count: staked_count as u64,
+        mission_completed: true,
+        staked_nfts: [v1::StakedData::default(); STAKE_MAX_COUNT],
+    };
+    for (i, staked) in user_pool.staked_nfts[..staked_count].iter_mut().enumerate() {
+        *staked = v1::StakedData {
+            bear_mint: Pubkey::new_unique(),
+            bear_id: 7_000 + i as u64,
+            box_mint: Pubkey::new_unique(),
+            box_id: if i % 3 == 0 { 0 } else { 9_000 + i as u64 },
+            staked_time: 1_640_000_000 + i as i64 * 3_600,
+        };
+    }
+
+    let mut data = UserPool::discriminator().to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&user_pool));
+    data.resize(USER_POOL_V1_SIZE, 0);
+    (user_pool, data)
+}
+
+#[test]
+fn v1_layout_is_packed() {
+    assert_eq!(std::mem::size_of::<v1::StakedData>(), 88);
+    assert_eq!(
+        std::mem::size_of::<v1::UserPool>(),
+        57 + 88 * STAKE_MAX_COUNT
+    );
+    assert!(8 + std::mem::size_of::<v1::UserPool>() <= USER_POOL_V1_SIZE);
+}
+
+#[test]
+fn migration_keeps_every_field_of_a_v1_pool() {
+    for staked_count in [0, 1, 2, STAKE_MAX_COUNT] {
+        let (old, data) = v1_account(staked_count);
+
+        let unpacked = UserPoolV1::unpack(&data).unwrap();
+        let mut user_pool = UserPool::default();
+        user_pool.migrate_from(&unpacked);
+
+        assert_eq!({ user_pool.version }, USER_POOL_VERSION);
+        assert_eq!({ user_pool.owner }, { old.owner });
+        assert_eq!({ user_pool.pool_id }, 0);
+        assert_eq!({ user_pool.last_claimed_time }, { old.last_claimed_time });
+        assert_eq!({ user_pool.pending_reward }, { old.pending_reward });
+        assert_eq!({ user_pool.staked_count }, { old.staked_count });
+        assert_eq!({ user_pool.mission_completed }, { old.mission_completed });
+        assert_eq!({ user_pool.claim_delegate }, Pubkey::default());
+        assert_eq!({ user_pool.reward_destination }, Pubkey::default());
+        assert_eq!({ user_pool.points }, 0);
+
+        for i in 0..STAKE_MAX_COUNT {
+            let (staked, before) = (user_pool.staked_nfts[i], old.staked_nfts[i]);
+            if i >= staked_count {
+                assert_eq!({ staked.bear_mint }, Pubkey::default());
+                continue;
+            }
+            assert_eq!({ staked.bear_mint }, { before.bear_mint });
+            assert_eq!({ staked.bear_id }, { before.bear_id });
+            assert_eq!({ staked.box_mint }, { before.box_mint });
+            assert_eq!({ staked.box_id }, { before.box_id });
+            assert_eq!({ staked.staked_time }, { before.staked_time });
+            assert_eq!({ staked.reward_rate }, 0);
+            assert_eq!({ staked.rent_start }, 0);
+            assert_eq!({ staked.rent_end }, 0);
+        }
+    }
+}
+
+#[test]
+fn unpack_rejects_accounts_of_another_size_or_count() {
+    let (_, data) = v1_account(1);
+    assert!(UserPoolV1::unpack(&data[..USER_POOL_V1_SIZE - 1]).is_err());
+
+    let (_, mut data) = v1_account(1);
+    data[8 + 48..8 + 56].copy_from_slice(&(STAKE_MAX_COUNT as u64 + 1).to_le_bytes());
+    assert!(UserPoolV1::unpack(&data).is_err());
+}